@@ -1,4 +1,5 @@
 use crate::direction::Direction;
+use std::str::FromStr;
 
 #[derive(Hash, Eq, PartialEq, Clone, Copy)]
 pub struct Coordinate(pub i32, pub i32);
@@ -23,6 +24,22 @@ impl core::ops::Add<Direction> for Coordinate {
     }
 }
 
+impl core::ops::Add for Coordinate {
+    type Output = Coordinate;
+
+    fn add(self, other: Coordinate) -> Self::Output {
+        Coordinate(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl core::ops::Sub for Coordinate {
+    type Output = Coordinate;
+
+    fn sub(self, other: Coordinate) -> Self::Output {
+        Coordinate(self.0 - other.0, self.1 - other.1)
+    }
+}
+
 impl PartialOrd for Coordinate {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         (self.0, self.1).partial_cmp(&(other.0, other.1))
@@ -35,6 +52,59 @@ impl Ord for Coordinate {
     }
 }
 
+impl Coordinate {
+    pub fn distance(&self, other: &Coordinate) -> f32 {
+        let dx = (other.0 - self.0) as f32;
+        let dy = (other.1 - self.1) as f32;
+        f32::sqrt(dx * dx + dy * dy)
+    }
+
+    // returns this coordinate in one of the 8 dihedral orientations, 0..4 are
+    // the quarter turns and 4..8 are the same turns applied after a mirror in x
+    pub fn orient(&self, orientation: usize) -> Coordinate {
+        let (x, y) = match orientation / 4 {
+            0 => (self.0, self.1),
+            1 => (-self.0, self.1),
+            _ => panic!("unknown 2d orientation: {}", orientation),
+        };
+
+        match orientation % 4 {
+            0 => Coordinate(x, y),
+            1 => Coordinate(-y, x),
+            2 => Coordinate(-x, -y),
+            _ => Coordinate(y, -x),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseCoordinateError;
+
+// converts a string like 1,2 to a coordinate
+impl FromStr for Coordinate {
+    type Err = ParseCoordinateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(',').map(|s| s.trim()).collect();
+        if parts.len() != 2 {
+            return Err(ParseCoordinateError);
+        }
+
+        let x = parts
+            .first()
+            .ok_or(ParseCoordinateError)?
+            .parse::<i32>()
+            .map_err(|_| ParseCoordinateError)?;
+        let y = parts
+            .get(1)
+            .ok_or(ParseCoordinateError)?
+            .parse::<i32>()
+            .map_err(|_| ParseCoordinateError)?;
+
+        Ok(Coordinate(x, y))
+    }
+}
+
 pub fn from_string(input: &str) -> Vec<(Coordinate, char)> {
     input
         .lines()
@@ -70,4 +140,22 @@ mod tests {
         assert_eq!(Coordinate(1, 1), second.0);
         assert_eq!('f', second.1);
     }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Coordinate(1, 2), Coordinate::from_str("1,2").unwrap());
+        assert_eq!(Coordinate(-1, 2), Coordinate::from_str("-1, 2").unwrap());
+        assert_eq!(Err(ParseCoordinateError), Coordinate::from_str("1,2,3"));
+    }
+
+    #[test]
+    fn orientations() {
+        let base = Coordinate(1, 2);
+        let orientations: std::collections::HashSet<_> =
+            (0..8).map(|orientation| base.orient(orientation)).collect();
+
+        assert_eq!(8, orientations.len());
+        assert_eq!(Coordinate(-2, 1), base.orient(1));
+        assert_eq!(Coordinate(-1, 2), base.orient(4));
+    }
 }
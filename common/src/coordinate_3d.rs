@@ -34,6 +34,44 @@ impl Coordinate3D {
         let dz = (other.2 - self.2) as f32;
         f32::sqrt(dx * dx + dy * dy + dz * dz)
     }
+
+    // returns this coordinate in one of the 24 rotations, first picking which of
+    // the six directions x faces, then one of four quarter turns around it
+    pub fn orient(&self, orientation: usize) -> Coordinate3D {
+        let Coordinate3D(x, y, z) = *self;
+        let (x, y, z) = match orientation / 4 {
+            0 => (x, y, z),
+            1 => (-x, -y, z),
+            2 => (y, -x, z),
+            3 => (-y, x, z),
+            4 => (z, y, -x),
+            5 => (-z, y, x),
+            _ => panic!("unknown 3d orientation: {}", orientation),
+        };
+
+        match orientation % 4 {
+            0 => Coordinate3D(x, y, z),
+            1 => Coordinate3D(x, -z, y),
+            2 => Coordinate3D(x, -y, -z),
+            _ => Coordinate3D(x, z, -y),
+        }
+    }
+}
+
+impl core::ops::Add for Coordinate3D {
+    type Output = Coordinate3D;
+
+    fn add(self, other: Coordinate3D) -> Self::Output {
+        Coordinate3D(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl core::ops::Sub for Coordinate3D {
+    type Output = Coordinate3D;
+
+    fn sub(self, other: Coordinate3D) -> Self::Output {
+        Coordinate3D(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -192,4 +230,15 @@ mod tests {
 
     //    assert_eq!(24, rotations.len());
     //}
+
+    #[test]
+    fn orientations() {
+        let base = Coordinate3D(1, 2, 3);
+        let orientations: std::collections::HashSet<_> = (0..24)
+            .map(|orientation| base.orient(orientation))
+            .collect();
+
+        assert_eq!(24, orientations.len());
+        assert_eq!(base, base.orient(0));
+    }
 }
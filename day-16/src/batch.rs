@@ -0,0 +1,127 @@
+use crate::error::DecodeError;
+use crate::eval::EvalError;
+use crate::parse;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LineError {
+    Decode(DecodeError),
+    Eval(EvalError),
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineError::Decode(error) => write!(f, "{}", error),
+            LineError::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+// one transmission out of a batch, number counts lines from 1 including the skipped ones
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Line {
+    pub number: usize,
+    pub result: Result<(u64, u64), LineError>,
+}
+
+// lines look like `3: version sum 16, value 15`
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok((version_sum, value)) => write!(
+                f,
+                "{}: version sum {}, value {}",
+                self.number, version_sum, value
+            ),
+            Err(error) => write!(f, "{}: error: {}", self.number, error),
+        }
+    }
+}
+
+// decodes a transmission per line, anything after a # is a comment and lines left blank
+// are skipped, a bad line doesn't stop the lines after it
+pub fn batch(input: &str) -> impl Iterator<Item = Line> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let transmission = line.split('#').next().unwrap().trim();
+            match transmission.is_empty() {
+                true => None,
+                false => Some((index + 1, transmission)),
+            }
+        })
+        .map(|(number, transmission)| Line {
+            number,
+            result: parse(transmission)
+                .map_err(LineError::Decode)
+                .and_then(|packet| {
+                    let value = packet.apply_checked().map_err(LineError::Eval)?;
+                    Ok((packet.version_sum(), value))
+                }),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DecodeErrorKind;
+    use crate::eval::EvalErrorKind;
+    use crate::operator::Operator;
+
+    #[test]
+    fn test_batch() {
+        let input = "\
+# the examples from part one
+8A004A801A8002F478
+620080001611562C8802118E34   # with a trailing comment
+
+  C200B40A82
+D2FE
+";
+        let lines: Vec<_> = batch(input).collect();
+        assert_eq!(4, lines.len());
+        assert_eq!(
+            vec![2, 3, 5, 6],
+            lines.iter().map(|line| line.number).collect::<Vec<_>>()
+        );
+        assert_eq!(Ok((16, 15)), lines[0].result);
+        assert_eq!(Ok((14, 3)), lines[2].result);
+        assert_eq!(
+            Err(LineError::Decode(DecodeError::new(
+                DecodeErrorKind::Truncated,
+                16
+            ))),
+            lines[3].result
+        );
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let overflow = crate::expression::compile("18446744073709551615 + 1", 0).unwrap();
+        let lines: Vec<_> = batch(&format!("{}\nC200B40A82\n", overflow)).collect();
+        assert_eq!(
+            Err(LineError::Eval(EvalError {
+                kind: EvalErrorKind::Overflow(Operator::Sum),
+                path: vec![],
+            })),
+            lines[0].result
+        );
+        assert_eq!(Ok((14, 3)), lines[1].result);
+    }
+
+    #[test]
+    fn test_rendering() {
+        let lines: Vec<_> = batch("C200B40A82\nD2FE\n").map(|l| l.to_string()).collect();
+        assert_eq!(
+            vec![
+                "1: version sum 14, value 3",
+                "2: error: transmission ended unexpectedly at bit 16",
+            ],
+            lines
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+const LIMB_BITS: usize = 32;
+
+// an unsigned integer of any size, stored as little endian 32 bit limbs with no
+// trailing zero limbs so that equal values always have equal representations
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // number of significant bits, zero for zero
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * LIMB_BITS - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    // returns the 4 bit group at index, counting up from the least significant
+    pub fn nibble(&self, index: usize) -> u8 {
+        let limb = self.limbs.get(index * 4 / LIMB_BITS).unwrap_or(&0);
+        ((limb >> (index * 4 % LIMB_BITS)) & 0xF) as u8
+    }
+
+    // shifts the value up 4 bits and places nibble in the gap, how literals are decoded
+    pub fn push_nibble(&mut self, nibble: u8) {
+        self.multiply_add(16, nibble as u32);
+    }
+
+    fn multiply_add(&mut self, multiplier: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for limb in self.limbs.iter_mut() {
+            let current = *limb as u64 * multiplier as u64 + carry;
+            *limb = current as u32;
+            carry = current >> LIMB_BITS;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        self.to_u128().and_then(|value| u64::try_from(value).ok())
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0, |value, limb| value << LIMB_BITS | *limb as u128),
+        )
+    }
+
+    // divides in place by a small divisor, returning the remainder
+    fn divide(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = remainder << LIMB_BITS | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        self.normalize();
+        remainder as u32
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut number = BigUint {
+            limbs: vec![value as u32, (value >> LIMB_BITS) as u32],
+        };
+        number.normalize();
+        number
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(value: u128) -> Self {
+        let mut number = BigUint {
+            limbs: (0..4)
+                .map(|index| (value >> (index * LIMB_BITS)) as u32)
+                .collect(),
+        };
+        number.normalize();
+        number
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseBigUintError;
+
+// converts a string of decimal digits like 2021 to a number
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBigUintError);
+        }
+
+        let mut number = BigUint::default();
+        for character in s.chars() {
+            let digit = character.to_digit(10).ok_or(ParseBigUintError)?;
+            number.multiply_add(10, digit);
+        }
+        Ok(number)
+    }
+}
+
+impl std::ops::Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+        for index in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(index).unwrap_or(&0) as u64
+                + *other.limbs.get(index).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> LIMB_BITS;
+        }
+        limbs.push(carry as u32);
+
+        let mut number = BigUint { limbs };
+        number.normalize();
+        number
+    }
+}
+
+impl std::ops::Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (left_index, left) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (right_index, right) in other.limbs.iter().enumerate() {
+                let current =
+                    limbs[left_index + right_index] as u64 + *left as u64 * *right as u64 + carry;
+                limbs[left_index + right_index] = current as u32;
+                carry = current >> LIMB_BITS;
+            }
+            limbs[left_index + other.limbs.len()] = carry as u32;
+        }
+
+        let mut number = BigUint { limbs };
+        number.normalize();
+        number
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // peel off nine decimal digits at a time, least significant first
+        let mut remaining = self.clone();
+        let mut chunks = vec![remaining.divide(1_000_000_000)];
+        while !remaining.is_zero() {
+            chunks.push(remaining.divide(1_000_000_000));
+        }
+
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert!(BigUint::from(0u64).is_zero());
+        assert_eq!(Some(u64::MAX), BigUint::from(u64::MAX).to_u64());
+        assert_eq!(None, BigUint::from(u64::MAX as u128 + 1).to_u64());
+        assert_eq!(Some(u128::MAX), BigUint::from(u128::MAX).to_u128());
+        assert_eq!(64, BigUint::from(u64::MAX).bits());
+        assert_eq!(0, BigUint::default().bits());
+    }
+
+    #[test]
+    fn test_nibbles() {
+        let mut number = BigUint::default();
+        for nibble in [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0xA] {
+            number.push_nibble(nibble);
+        }
+        assert_eq!(Some(0x123456789A), number.to_u64());
+        assert_eq!(0xA, number.nibble(0));
+        assert_eq!(0x2, number.nibble(8));
+        assert_eq!(0x0, number.nibble(20));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let big = BigUint::from(u64::MAX);
+        assert_eq!(BigUint::from(u64::MAX as u128 * 2), &big + &big);
+        assert_eq!(
+            BigUint::from(u64::MAX as u128 * u64::MAX as u128),
+            &big * &big
+        );
+        assert_eq!(BigUint::default(), &big * &BigUint::default());
+
+        let squared = &big * &big;
+        assert_eq!(
+            "115792089237316195398462578067141184799968521174335529155754622898352762650625",
+            (&squared * &squared).to_string()
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let small = BigUint::from(5u64);
+        let large = BigUint::from(u64::MAX as u128 + 5);
+        assert!(small < large);
+        assert!(BigUint::from(6u64) > small);
+        assert_eq!(Some(&large), [small.clone(), large.clone()].iter().max());
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(BigUint::from(2021u64)), BigUint::from_str("2021"));
+        assert_eq!(Ok(BigUint::default()), BigUint::from_str("000"));
+        assert_eq!(
+            Ok(BigUint::from(u128::MAX)),
+            BigUint::from_str(&u128::MAX.to_string())
+        );
+        assert_eq!(Err(ParseBigUintError), BigUint::from_str(""));
+        assert_eq!(Err(ParseBigUintError), BigUint::from_str("12a"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("0", BigUint::default().to_string());
+        assert_eq!("2021", BigUint::from(2021u64).to_string());
+        assert_eq!("1000000000", BigUint::from(1_000_000_000u64).to_string());
+        assert_eq!(u128::MAX.to_string(), BigUint::from(u128::MAX).to_string());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::eval::{EvalError, EvalErrorKind};
+use crate::operator::Operator;
+use crate::{Packet, Payload};
+use std::fmt;
+
+// the operators take their operands off the top of the stack and push their result,
+// variadic ones say how many operands they take
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Instruction {
+    Push(u64),
+    Sum(usize),
+    Product(usize),
+    Minimum(usize),
+    Maximum(usize),
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Push(value) => write!(f, "push {}", value),
+            Instruction::Sum(count) => write!(f, "sum {}", count),
+            Instruction::Product(count) => write!(f, "product {}", count),
+            Instruction::Minimum(count) => write!(f, "min {}", count),
+            Instruction::Maximum(count) => write!(f, "max {}", count),
+            Instruction::GreaterThan => write!(f, "gt"),
+            Instruction::LessThan => write!(f, "lt"),
+            Instruction::EqualTo => write!(f, "eq"),
+        }
+    }
+}
+
+// a packet flattened into postfix order, only compile makes these so every program
+// leaves exactly one value on the stack
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+}
+
+// one instruction per line
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+// literals have to fit in a u64 just like they do for apply, and operators need the
+// sub-packets they take so the program can never run short of operands
+pub fn compile(packet: &Packet) -> Result<Program, EvalError> {
+    let mut instructions = Vec::new();
+    packet.try_fold(|packet, path, _: Vec<()>| {
+        let instruction = match &packet.payload {
+            Payload::Literal(value) => match value.to_u64() {
+                Some(value) => Instruction::Push(value),
+                None => {
+                    return Err(EvalError {
+                        kind: EvalErrorKind::LiteralTooLarge,
+                        path: path.to_vec(),
+                    })
+                }
+            },
+            Payload::Operator(operator, packets) if !operator.accepts(packets.len()) => {
+                return Err(EvalError {
+                    kind: EvalErrorKind::WrongArity(*operator, packets.len()),
+                    path: path.to_vec(),
+                })
+            }
+            Payload::Operator(operator, packets) => match operator {
+                Operator::Sum => Instruction::Sum(packets.len()),
+                Operator::Product => Instruction::Product(packets.len()),
+                Operator::Minimum => Instruction::Minimum(packets.len()),
+                Operator::Maximum => Instruction::Maximum(packets.len()),
+                Operator::GreaterThan => Instruction::GreaterThan,
+                Operator::LessThan => Instruction::LessThan,
+                Operator::EqualTo => Instruction::EqualTo,
+            },
+        };
+        instructions.push(instruction);
+        Ok(())
+    })?;
+
+    Ok(Program { instructions })
+}
+
+// instruction is the index of the sum or product that overflowed
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VmError {
+    pub operator: Operator,
+    pub instruction: usize,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} overflowed at instruction {}",
+            self.operator, self.instruction
+        )
+    }
+}
+
+impl std::error::Error for VmError {}
+
+// holds on to its stack between runs so evaluating many programs doesn't keep allocating
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<u64>,
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm { stack: Vec::new() }
+    }
+
+    pub fn run(&mut self, program: &Program) -> Result<u64, VmError> {
+        self.stack.clear();
+
+        for (index, instruction) in program.instructions.iter().enumerate() {
+            let overflow = |operator| VmError {
+                operator,
+                instruction: index,
+            };
+
+            let value = match *instruction {
+                Instruction::Push(value) => value,
+                Instruction::Sum(count) => self
+                    .pop(count)
+                    .try_fold(0u64, |total, value| total.checked_add(value))
+                    .ok_or(overflow(Operator::Sum))?,
+                Instruction::Product(count) => self
+                    .pop(count)
+                    .try_fold(1u64, |total, value| total.checked_mul(value))
+                    .ok_or(overflow(Operator::Product))?,
+                Instruction::Minimum(count) => self.pop(count).min().unwrap(),
+                Instruction::Maximum(count) => self.pop(count).max().unwrap(),
+                Instruction::GreaterThan => self.compare(|left, right| left > right),
+                Instruction::LessThan => self.compare(|left, right| left < right),
+                Instruction::EqualTo => self.compare(|left, right| left == right),
+            };
+            self.stack.push(value);
+        }
+
+        Ok(self.stack.pop().unwrap())
+    }
+
+    fn pop(&mut self, count: usize) -> std::vec::Drain<'_, u64> {
+        let start = self.stack.len() - count;
+        self.stack.drain(start..)
+    }
+
+    fn compare(&mut self, test: fn(u64, u64) -> bool) -> u64 {
+        let right = self.stack.pop().unwrap();
+        let left = self.stack.pop().unwrap();
+        test(left, right) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::to_packet;
+    use crate::fixtures::{EXAMPLES, INPUT};
+    use crate::parse;
+
+    #[test]
+    fn test_compile() {
+        let program = compile(&parse("9C0141080250320F1802104A08").unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                Instruction::Push(1),
+                Instruction::Push(3),
+                Instruction::Sum(2),
+                Instruction::Push(2),
+                Instruction::Push(2),
+                Instruction::Product(2),
+                Instruction::EqualTo,
+            ],
+            program.instructions()
+        );
+        assert_eq!(
+            "push 1\npush 3\nsum 2\npush 2\npush 2\nproduct 2\neq\n",
+            program.to_string()
+        );
+    }
+
+    #[test]
+    fn test_matches_apply() {
+        let mut vm = Vm::new();
+        for example in EXAMPLES.iter().chain([&INPUT]) {
+            let packet = parse(example).unwrap();
+            let program = compile(&packet).unwrap();
+            assert_eq!(Ok(packet.apply()), vm.run(&program), "{}", example);
+        }
+
+        for source in ["min(3, 4*5) + (7 > 2)", "max(1, 2, 3) * (4 < 5) * (6 == 6)"] {
+            let packet = to_packet(source, || 0).unwrap();
+            assert_eq!(Ok(packet.apply()), vm.run(&compile(&packet).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let packet = to_packet("1 + 2 * 18446744073709551615", || 0).unwrap();
+        let error = Vm::new().run(&compile(&packet).unwrap()).unwrap_err();
+        assert_eq!(
+            VmError {
+                operator: Operator::Product,
+                instruction: 3,
+            },
+            error
+        );
+        assert_eq!("Product overflowed at instruction 3", error.to_string());
+
+        let packet = to_packet("1 + 18446744073709551616", || 0).unwrap();
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::LiteralTooLarge,
+                path: vec![1],
+            }),
+            compile(&packet)
+        );
+
+        let mut packet = to_packet("min(1, 2 > 3)", || 0).unwrap();
+        if let Payload::Operator(_, packets) = &mut packet.payload {
+            if let Payload::Operator(_, packets) = &mut packets[1].payload {
+                packets.pop();
+            }
+        }
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::WrongArity(Operator::GreaterThan, 1),
+                path: vec![1],
+            }),
+            compile(&packet)
+        );
+    }
+}
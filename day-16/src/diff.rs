@@ -0,0 +1,271 @@
+use crate::bignum::BigUint;
+use crate::error::DecodeError;
+use crate::eval::EvalError;
+use crate::operator::Operator;
+use crate::{parse, Packet, Payload};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Version(u64, u64),
+    Operator(Operator, Operator),
+    Literal(BigUint, BigUint),
+}
+
+// path leads to the packet from the outermost one, sub-packets are matched up by
+// position so a removed packet's path is from the old tree and an added one's the new
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Change {
+    pub path: Vec<usize>,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+    pub before: BigUint,
+    pub after: BigUint,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DiffError {
+    Decode(DecodeError),
+    Eval(EvalError),
+}
+
+impl From<DecodeError> for DiffError {
+    fn from(error: DecodeError) -> Self {
+        DiffError::Decode(error)
+    }
+}
+
+impl From<EvalError> for DiffError {
+    fn from(error: EvalError) -> Self {
+        DiffError::Eval(error)
+    }
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::Decode(error) => write!(f, "{}", error),
+            DiffError::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+impl Diff {
+    pub fn value_changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+// changes come out in pre-order, a packet that swaps between a literal and an operator
+// is reported as removed and added rather than compared any further, both packets have
+// to evaluate for the values to be compared
+pub fn diff(before: &Packet, after: &Packet) -> Result<Diff, EvalError> {
+    let values = (before.try_apply()?, after.try_apply()?);
+    let mut changes = Vec::new();
+    // sub-packets matched up by position, missing on one side when the counts differ
+    let mut pending = vec![(Vec::new(), Some(before), Some(after))];
+
+    while let Some((path, before, after)) = pending.pop() {
+        let (before, after) = match (before, after) {
+            (Some(before), Some(after)) => (before, after),
+            (Some(_), None) => {
+                changes.push(Change {
+                    path,
+                    kind: ChangeKind::Removed,
+                });
+                continue;
+            }
+            (None, _) => {
+                changes.push(Change {
+                    path,
+                    kind: ChangeKind::Added,
+                });
+                continue;
+            }
+        };
+
+        let change = |kind| Change {
+            path: path.clone(),
+            kind,
+        };
+
+        if before.version != after.version {
+            changes.push(change(ChangeKind::Version(before.version, after.version)));
+        }
+
+        match (&before.payload, &after.payload) {
+            (Payload::Literal(old), Payload::Literal(new)) => {
+                if old != new {
+                    changes.push(change(ChangeKind::Literal(old.clone(), new.clone())));
+                }
+            }
+            (Payload::Operator(old, old_packets), Payload::Operator(new, new_packets)) => {
+                if old != new {
+                    changes.push(change(ChangeKind::Operator(*old, *new)));
+                }
+
+                // pushed last first so they come back off in order
+                for index in (0..old_packets.len().max(new_packets.len())).rev() {
+                    pending.push((
+                        [path.as_slice(), &[index]].concat(),
+                        old_packets.get(index),
+                        new_packets.get(index),
+                    ));
+                }
+            }
+            _ => {
+                changes.push(change(ChangeKind::Removed));
+                changes.push(change(ChangeKind::Added));
+            }
+        }
+    }
+
+    Ok(Diff {
+        changes,
+        before: values.0,
+        after: values.1,
+    })
+}
+
+pub fn diff_transmissions(before: &str, after: &str) -> Result<Diff, DiffError> {
+    Ok(diff(&parse(before)?, &parse(after)?)?)
+}
+
+// lines look like `[0, 1]  literal 3 -> 4`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<12} ", format!("{:?}", self.path))?;
+        match &self.kind {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Version(old, new) => write!(f, "version {} -> {}", old, new),
+            ChangeKind::Operator(old, new) => write!(f, "operator {} -> {}", old, new),
+            ChangeKind::Literal(old, new) => write!(f, "literal {} -> {}", old, new),
+        }
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        match self.value_changed() {
+            true => writeln!(f, "value {} -> {}", self.before, self.after),
+            false => writeln!(f, "value unchanged at {}", self.before),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::EvalErrorKind;
+    use crate::expression::to_packet;
+
+    fn packet(source: &str) -> Packet {
+        to_packet(source, || 0).unwrap()
+    }
+
+    fn change(path: &[usize], kind: ChangeKind) -> Change {
+        Change {
+            path: path.to_vec(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_identical() {
+        let diff =
+            diff_transmissions("9C0141080250320F1802104A08", "9C0141080250320F1802104A08").unwrap();
+        assert!(diff.changes.is_empty());
+        assert!(!diff.value_changed());
+
+        // the same tree encoded differently isn't a change
+        let before = parse("C200B40A82").unwrap();
+        let after = crate::optimizer::optimize(&before, |_, _| false)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert!(diff_transmissions("C200B40A82", &after)
+            .unwrap()
+            .changes
+            .is_empty());
+    }
+
+    #[test]
+    fn test_changes() {
+        let diff = diff(
+            &packet("(1 + 3) == (2 * 2)"),
+            &packet("(1 * 3) == (2 * 5 * 7)"),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                change(&[0], ChangeKind::Operator(Operator::Sum, Operator::Product)),
+                change(
+                    &[1, 1],
+                    ChangeKind::Literal(BigUint::from(2u64), BigUint::from(5u64))
+                ),
+                change(&[1, 2], ChangeKind::Added),
+            ],
+            diff.changes
+        );
+        assert_eq!(BigUint::from(1u64), diff.before);
+        assert_eq!(BigUint::from(0u64), diff.after);
+        assert!(diff.value_changed());
+    }
+
+    #[test]
+    fn test_versions_and_shape() {
+        let mut before = packet("max(1, 2 + 3, 4)");
+        before.version = 3;
+        let after = packet("max(1, 5)");
+
+        let diff = diff(&before, &after).unwrap();
+        assert_eq!(
+            vec![
+                change(&[], ChangeKind::Version(3, 0)),
+                change(&[1], ChangeKind::Removed),
+                change(&[1], ChangeKind::Added),
+                change(&[2], ChangeKind::Removed),
+            ],
+            diff.changes
+        );
+        assert!(!diff.value_changed());
+        assert_eq!(
+            "[]           version 3 -> 0\n\
+             [1]          removed\n\
+             [1]          added\n\
+             [2]          removed\n\
+             value unchanged at 5\n",
+            diff.to_string()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        // a less than operator left with a single sub-packet
+        let mut after = packet("1 < 2");
+        if let Payload::Operator(_, packets) = &mut after.payload {
+            packets.pop();
+        }
+        assert_eq!(
+            Some(EvalErrorKind::WrongArity(Operator::LessThan, 1)),
+            diff(&packet("1 < 2"), &after).err().map(|error| error.kind)
+        );
+
+        assert!(matches!(
+            diff_transmissions("D2FE28", "38002C408"),
+            Err(DiffError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::operator::Operator;
+use crate::reader::HexReader;
+use crate::{read_header_fields, Body, Ending};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldKind {
+    Version,
+    TypeId,
+    LengthType,
+    BitLength,
+    PacketCount,
+    LiteralGroup,
+    Padding,
+}
+
+// a single field of the transmission, depth is how deeply nested its packet is
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Field {
+    pub kind: FieldKind,
+    pub offset: usize,
+    pub width: usize,
+    pub value: u64,
+    pub depth: usize,
+}
+
+// every field read before the transmission ended or stopped making sense
+pub struct Disassembly {
+    pub fields: Vec<Field>,
+    pub error: Option<DecodeError>,
+}
+
+pub fn disassemble(input: &str) -> Disassembly {
+    let mut fields = Vec::new();
+    let error = HexReader::new(input)
+        .and_then(|mut reader| {
+            reader.position = walk_packets(&reader, &mut fields)?;
+            walk_padding(&mut reader, &mut fields)
+        })
+        .err();
+
+    Disassembly { fields, error }
+}
+
+// reads every header in transmission order with an explicit stack of open operators, like
+// the parser, so deep transmissions can't overflow the stack. returns where the outermost
+// packet ends
+fn walk_packets(reader: &HexReader, fields: &mut Vec<Field>) -> Result<usize, DecodeError> {
+    // each open operator's ending along with how many of its sub-packets have been read
+    let mut open: Vec<(Ending, u64)> = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let depth = open.len();
+        let header = read_header_fields(reader, position, |kind, offset, width, value| {
+            fields.push(Field {
+                kind,
+                offset,
+                width,
+                value,
+                depth,
+            })
+        })?;
+        position = header.end;
+
+        let mut finished = match header.body {
+            Body::Literal(_) => true,
+            Body::Operator(_, ending) => {
+                open.push((ending, 0));
+                false
+            }
+        };
+
+        // count the finished packet against its parent, closing every parent it completes
+        loop {
+            let (ending, count) = match open.last_mut() {
+                Some(frame) => frame,
+                None => return Ok(position),
+            };
+
+            if finished {
+                *count += 1;
+                if let Ending::Position(ending) = *ending {
+                    if position > ending {
+                        return Err(DecodeError::new(DecodeErrorKind::LengthMismatch, position));
+                    }
+                }
+            }
+
+            let complete = match *ending {
+                Ending::Position(ending) => position >= ending,
+                Ending::Count(total) => *count == total,
+            };
+            if !complete {
+                break;
+            }
+            open.pop();
+            finished = true;
+        }
+    }
+}
+
+fn walk_padding(reader: &mut HexReader, fields: &mut Vec<Field>) -> Result<(), DecodeError> {
+    while reader.remaining() > 0 {
+        let offset = reader.position;
+        let width = reader.remaining().min(64);
+        let value = reader.read_decimal(width)?;
+        fields.push(Field {
+            kind: FieldKind::Padding,
+            offset,
+            width,
+            value,
+            depth: 0,
+        });
+    }
+    Ok(())
+}
+
+impl Field {
+    fn describe(&self) -> String {
+        match self.kind {
+            FieldKind::Version => format!("version {}", self.value),
+            FieldKind::TypeId => match Operator::from_type_id(self.value) {
+                Some(operator) => format!("type id {} ({})", self.value, operator),
+                None => format!("type id {} (literal)", self.value),
+            },
+            FieldKind::LengthType => match self.value {
+                0 => "length type 0 (bit length)".to_string(),
+                _ => "length type 1 (packet count)".to_string(),
+            },
+            FieldKind::BitLength => format!("sub-packet bits {}", self.value),
+            FieldKind::PacketCount => format!("sub-packet count {}", self.value),
+            FieldKind::LiteralGroup => format!(
+                "literal group {:X} ({})",
+                self.value & 0xF,
+                match self.value & 0b10000 {
+                    0 => "last",
+                    _ => "more",
+                }
+            ),
+            FieldKind::Padding => match self.value {
+                0 => "padding".to_string(),
+                _ => "padding (not zero)".to_string(),
+            },
+        }
+    }
+}
+
+// lines look like `6..11     1 0111      literal group 7 (more)`
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = match self.kind {
+            FieldKind::LiteralGroup => format!("{} {:04b}", self.value >> 4, self.value & 0xF),
+            _ => format!("{:0width$b}", self.value, width = self.width),
+        };
+        write!(
+            f,
+            "{:>12}  {:indent$}{:<16} {}",
+            format!("{}..{}", self.offset, self.offset + self.width),
+            "",
+            bits,
+            self.describe(),
+            indent = self.depth * 2
+        )
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            writeln!(f, "{}", field)?;
+        }
+        match &self.error {
+            Some(error) => writeln!(f, "error: {}", error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::deep_transmission;
+
+    fn field(kind: FieldKind, offset: usize, width: usize, value: u64, depth: usize) -> Field {
+        Field {
+            kind,
+            offset,
+            width,
+            value,
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_literal() {
+        let disassembly = disassemble("D2FE28");
+        assert_eq!(None, disassembly.error);
+        assert_eq!(
+            vec![
+                field(FieldKind::Version, 0, 3, 6, 0),
+                field(FieldKind::TypeId, 3, 3, 4, 0),
+                field(FieldKind::LiteralGroup, 6, 5, 0b10111, 0),
+                field(FieldKind::LiteralGroup, 11, 5, 0b11110, 0),
+                field(FieldKind::LiteralGroup, 16, 5, 0b00101, 0),
+                field(FieldKind::Padding, 21, 3, 0, 0),
+            ],
+            disassembly.fields
+        );
+    }
+
+    #[test]
+    fn test_operator() {
+        let disassembly = disassemble("38006F45291200");
+        assert_eq!(None, disassembly.error);
+
+        let kinds: Vec<_> = disassembly.fields.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            vec![
+                FieldKind::Version,
+                FieldKind::TypeId,
+                FieldKind::LengthType,
+                FieldKind::BitLength,
+                FieldKind::Version,
+                FieldKind::TypeId,
+                FieldKind::LiteralGroup,
+                FieldKind::Version,
+                FieldKind::TypeId,
+                FieldKind::LiteralGroup,
+                FieldKind::LiteralGroup,
+                FieldKind::Padding,
+            ],
+            kinds
+        );
+        assert_eq!(
+            field(FieldKind::BitLength, 7, 15, 27, 0),
+            disassembly.fields[3]
+        );
+        assert_eq!(1, disassembly.fields[4].depth);
+        assert_eq!(
+            field(FieldKind::Padding, 49, 7, 0, 0),
+            disassembly.fields[11]
+        );
+    }
+
+    #[test]
+    fn test_rendering() {
+        let rendered = disassemble("EE00D40C823060").to_string();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!("        0..3  111              version 7", lines[0]);
+        assert_eq!(
+            "        3..6  011              type id 3 (maximum)",
+            lines[1]
+        );
+        assert_eq!(
+            "        6..7  1                length type 1 (packet count)",
+            lines[2]
+        );
+        assert_eq!(
+            "       7..18  00000000011      sub-packet count 3",
+            lines[3]
+        );
+        assert_eq!(
+            "      24..29    0 0001           literal group 1 (last)",
+            lines[6]
+        );
+        assert_eq!("      51..56  00000            padding", lines[13]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        // the 15 bit length promises more sub-packet bits than the transmission holds
+        let disassembly = disassemble("38006F452912");
+        assert_eq!(
+            Some(DecodeError::new(DecodeErrorKind::Truncated, 44)),
+            disassembly.error
+        );
+        assert_eq!(10, disassembly.fields.len());
+        assert!(disassembly
+            .to_string()
+            .ends_with("error: transmission ended unexpectedly at bit 44\n"));
+
+        let disassembly = disassemble("3Z");
+        assert_eq!(0, disassembly.fields.len());
+        assert_eq!(
+            Some(DecodeError::new(DecodeErrorKind::InvalidHexDigit('Z'), 4)),
+            disassembly.error
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let disassembly = disassemble(&deep_transmission(100_000));
+        assert_eq!(None, disassembly.error);
+        assert_eq!(100_000 * 4 + 4, disassembly.fields.len());
+        assert_eq!(
+            field(
+                FieldKind::LiteralGroup,
+                100_000 * 18 + 6,
+                5,
+                0b00111,
+                100_000
+            ),
+            disassembly.fields[100_000 * 4 + 2]
+        );
+    }
+}
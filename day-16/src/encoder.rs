@@ -0,0 +1,477 @@
+use crate::bignum::BigUint;
+use crate::visitor::PacketVisitor;
+use crate::{Packet, Payload};
+use std::fmt;
+
+// the largest version that fits in the 3 bit header field
+pub const MAX_VERSION: u64 = (1 << 3) - 1;
+const MAX_BIT_LENGTH: usize = (1 << 15) - 1;
+pub const MAX_SUBPACKET_COUNT: usize = (1 << 11) - 1;
+
+// the inverse of the HexReader, packs values of arbitrary width into bytes
+#[derive(Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    pub length: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> BitWriter {
+        BitWriter {
+            bytes: Vec::new(),
+            length: 0,
+        }
+    }
+
+    pub fn write(&mut self, value: u64, width: usize) {
+        for shift in (0..width).rev() {
+            if self.length.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> shift) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.length % 8);
+            self.length += 1;
+        }
+    }
+
+    pub fn append(&mut self, other: &BitWriter) {
+        for (index, byte) in other.bytes.iter().enumerate() {
+            let width = (other.length - index * 8).min(8);
+            self.write((*byte >> (8 - width)) as u64, width);
+        }
+    }
+
+    // renders every byte written so far, the last byte is zero padded
+    pub fn to_hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect()
+    }
+}
+
+// which length type operators are written with, standard measures sub-packets in bits
+// whenever they fit while compact counts them whenever it can, which is 4 bits shorter
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    Standard,
+    Compact,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EncodeErrorKind {
+    VersionTooLarge(u64),
+    // too many sub-packets to count, taking too many bits to measure even with every
+    // operator below written as compactly as possible
+    TooManySubpackets(usize, usize),
+}
+
+// path holds the index of each sub-packet taken from the outermost packet down
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncodeError {
+    pub kind: EncodeErrorKind,
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            EncodeErrorKind::VersionTooLarge(version) => {
+                write!(f, "version {} doesn't fit in 3 bits", version)?
+            }
+            EncodeErrorKind::TooManySubpackets(count, bits) => write!(
+                f,
+                "{} sub-packets over {} bits can't be counted or measured",
+                count, bits
+            )?,
+        }
+        write!(f, " at packet {:?}", self.path)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl Packet {
+    pub fn encode(&self) -> Result<String, EncodeError> {
+        Ok(self.to_bits(Encoding::Standard)?.to_hex())
+    }
+
+    pub fn to_bits(&self, encoding: Encoding) -> Result<BitWriter, EncodeError> {
+        let measure = self.measure()?;
+        let mut emit = Emit {
+            writer: BitWriter::new(),
+            layouts: &measure.layouts,
+            next: 0,
+            encodings: vec![encoding],
+        };
+        self.walk(&mut emit);
+        Ok(emit.writer)
+    }
+
+    // how many bits to_bits would write, without writing them
+    pub fn bit_length(&self, encoding: Encoding) -> Result<usize, EncodeError> {
+        let (standard, compact) = self.measure()?.bits;
+        match encoding {
+            Encoding::Standard => Ok(standard),
+            Encoding::Compact => Ok(compact),
+        }
+    }
+
+    fn measure(&self) -> Result<Measure, EncodeError> {
+        let mut measure = Measure {
+            layouts: Vec::new(),
+            open: Vec::new(),
+            bits: (0, 0),
+            error: None,
+        };
+        self.walk(&mut measure);
+        match measure.error.take() {
+            Some(error) => Err(error),
+            None => Ok(measure),
+        }
+    }
+}
+
+// how an operator is written, whether its sub-packets are counted rather than measured,
+// the bits they take and how they're written in turn
+#[derive(Clone, Copy)]
+struct Layout {
+    counted: bool,
+    body: usize,
+    subpackets: Encoding,
+}
+
+impl Layout {
+    fn bits(&self) -> usize {
+        7 + if self.counted { 11 } else { 15 } + self.body
+    }
+}
+
+// an operator laid out for each encoding
+#[derive(Clone, Copy)]
+struct Layouts {
+    standard: Layout,
+    compact: Layout,
+}
+
+// works out both layouts of every operator as it's left, innermost first, so every length
+// is known before anything is written. standard measures sub-packets whenever they fit and
+// counts them otherwise, falling back to the compact layout for an operator that has too
+// many sub-packets to count and too many bits to measure once they're written standard
+struct Measure {
+    // every packet in pre-order, None for literals
+    layouts: Vec<Option<Layouts>>,
+    // each open packet's index into layouts, with the bits its finished sub-packets take
+    // written standard and compact
+    open: Vec<(usize, usize, usize)>,
+    // the bits taken by the packet left last, which in the end is the outermost one
+    bits: (usize, usize),
+    // walks can't be stopped, so everything after the first error is skipped
+    error: Option<EncodeError>,
+}
+
+impl PacketVisitor for Measure {
+    fn enter(&mut self, packet: &Packet, path: &[usize]) {
+        if self.error.is_some() {
+            return;
+        }
+        if packet.version > MAX_VERSION {
+            self.error = Some(EncodeError {
+                kind: EncodeErrorKind::VersionTooLarge(packet.version),
+                path: path.to_vec(),
+            });
+            return;
+        }
+        self.open.push((self.layouts.len(), 0, 0));
+        self.layouts.push(None);
+    }
+
+    fn leave(&mut self, packet: &Packet, path: &[usize]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let (index, standard, compact) = self.open.pop().unwrap();
+        let bits = match &packet.payload {
+            Payload::Literal(value) => {
+                let bits = 6 + 5 * groups(value);
+                (bits, bits)
+            }
+            Payload::Operator(_, packets) => {
+                let countable = packets.len() <= MAX_SUBPACKET_COUNT;
+                if !countable && compact > MAX_BIT_LENGTH {
+                    self.error = Some(EncodeError {
+                        kind: EncodeErrorKind::TooManySubpackets(packets.len(), compact),
+                        path: path.to_vec(),
+                    });
+                    return;
+                }
+
+                let compact = Layout {
+                    counted: countable,
+                    body: compact,
+                    subpackets: Encoding::Compact,
+                };
+                let standard = match standard <= MAX_BIT_LENGTH || countable {
+                    true => Layout {
+                        counted: standard > MAX_BIT_LENGTH,
+                        body: standard,
+                        subpackets: Encoding::Standard,
+                    },
+                    false => compact,
+                };
+                self.layouts[index] = Some(Layouts { standard, compact });
+                (standard.bits(), compact.bits())
+            }
+        };
+
+        if let Some((_, standard, compact)) = self.open.last_mut() {
+            *standard += bits.0;
+            *compact += bits.1;
+        }
+        self.bits = bits;
+    }
+}
+
+// writes every packet in pre-order using the layouts measured for it
+struct Emit<'a> {
+    writer: BitWriter,
+    layouts: &'a [Option<Layouts>],
+    next: usize,
+    // how each open packet's sub-packets are written, starting with the outermost packet
+    encodings: Vec<Encoding>,
+}
+
+impl PacketVisitor for Emit<'_> {
+    fn enter(&mut self, packet: &Packet, _: &[usize]) {
+        let encoding = *self.encodings.last().unwrap();
+        let layouts = self.layouts[self.next];
+        self.next += 1;
+
+        self.writer.write(packet.version, 3);
+        self.writer.write(packet.type_id(), 3);
+        match &packet.payload {
+            Payload::Literal(value) => {
+                for group in (0..groups(value)).rev() {
+                    self.writer.write((group != 0) as u64, 1);
+                    self.writer.write(value.nibble(group) as u64, 4);
+                }
+                self.encodings.push(encoding);
+            }
+            Payload::Operator(_, packets) => {
+                let layouts = layouts.unwrap();
+                let layout = match encoding {
+                    Encoding::Standard => layouts.standard,
+                    Encoding::Compact => layouts.compact,
+                };
+                if layout.counted {
+                    self.writer.write(1, 1);
+                    self.writer.write(packets.len() as u64, 11);
+                } else {
+                    self.writer.write(0, 1);
+                    self.writer.write(layout.body as u64, 15);
+                }
+                self.encodings.push(layout.subpackets);
+            }
+        }
+    }
+
+    fn leave(&mut self, _: &Packet, _: &[usize]) {
+        self.encodings.pop();
+    }
+}
+
+// the 4 bit groups a literal is written in, zero still takes one
+fn groups(value: &BigUint) -> usize {
+    value.bits().div_ceil(4).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{deep_transmission, EXAMPLES};
+    use crate::generator::Generator;
+    use crate::operator::Operator;
+    use crate::{parse, LITERAL_TYPE_ID};
+
+    #[test]
+    fn test_bit_writer() {
+        let mut writer = BitWriter::new();
+        writer.write(6, 3);
+        writer.write(4, 3);
+        writer.write(0b101111111000101, 15);
+        assert_eq!(21, writer.length);
+        assert_eq!("D2FE28", writer.to_hex());
+
+        let mut appended = BitWriter::new();
+        appended.write(0b11, 2);
+        appended.append(&writer);
+        assert_eq!(23, appended.length);
+        assert_eq!("F4BF8A", appended.to_hex());
+    }
+
+    #[test]
+    fn test_encode_literal() {
+        let packet = parse("D2FE28").unwrap();
+        assert_eq!(Ok("D2FE28".to_string()), packet.encode());
+
+        let zero = Packet {
+            version: 0,
+            payload: Payload::Literal(BigUint::default()),
+        };
+        assert_eq!(zero, parse(&zero.encode().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_encode_count_length_type() {
+        let packet = Packet {
+            version: 1,
+            payload: Payload::Operator(
+                Operator::Sum,
+                (0..2000)
+                    .map(|_| Packet {
+                        version: 0,
+                        payload: Payload::Literal(BigUint::from(u64::MAX)),
+                    })
+                    .collect(),
+            ),
+        };
+        let encoded = packet.encode().unwrap();
+        // version 1, sum, length type 1 and the first bit of the 11 bit count
+        assert!(encoded.starts_with("23"));
+        assert_eq!(packet, parse(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        for example in EXAMPLES {
+            let packet = parse(example).unwrap();
+            let compact = packet.to_bits(Encoding::Compact).unwrap();
+            let operators = packet.fold(|packet, counts: Vec<usize>| {
+                counts.into_iter().sum::<usize>()
+                    + matches!(packet.payload, Payload::Operator(..)) as usize
+            });
+
+            assert_eq!(packet, parse(&compact.to_hex()).unwrap(), "{}", example);
+            assert_eq!(
+                packet.to_bits(Encoding::Standard).unwrap().length - 4 * operators,
+                compact.length
+            );
+            assert_eq!(Ok(compact.length), packet.bit_length(Encoding::Compact));
+        }
+    }
+
+    #[test]
+    fn test_examples_round_trip() {
+        for example in EXAMPLES {
+            let packet = parse(example).unwrap();
+            assert_eq!(
+                packet,
+                parse(&packet.encode().unwrap()).unwrap(),
+                "{}",
+                example
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_round_trip() {
+        let mut generator = Generator::new(0x2021_1216);
+        for _ in 0..500 {
+            let packet = generator.packet();
+            assert_eq!(packet, parse(&packet.encode().unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wide_operator() {
+        // a sum of 2048 sub-packets, too many to count, 500 of them counted maximums of a
+        // single literal. measuring the maximums as well would take it past 15 bits
+        let mut writer = BitWriter::new();
+        writer.write(0, 3);
+        writer.write(Operator::Sum.type_id(), 3);
+        writer.write(0, 1);
+        writer.write(500 * 29 + 1548 * 11, 15);
+        for index in 0..2048 {
+            if index < 500 {
+                writer.write(0, 3);
+                writer.write(Operator::Maximum.type_id(), 3);
+                writer.write(1, 1);
+                writer.write(1, 11);
+            }
+            writer.write(0, 3);
+            writer.write(LITERAL_TYPE_ID, 3);
+            writer.write(0b00001, 5);
+        }
+
+        let hex = writer.to_hex();
+        let packet = parse(&hex).unwrap();
+        assert_eq!(Ok(hex), packet.encode());
+        assert_eq!(
+            Ok(packet.bit_length(Encoding::Compact).unwrap()),
+            packet.bit_length(Encoding::Standard)
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // every level counts its one sub-packet, as compact does, while standard measures
+        // the levels whose sub-packets fit in 15 bits. packets are compared by their
+        // encodings since == recurses
+        let hex = deep_transmission(100_000);
+        let packet = parse(&hex).unwrap();
+        let compact = |packet: &Packet| packet.to_bits(Encoding::Compact).unwrap().to_hex();
+        assert_eq!(hex, compact(&packet));
+
+        let reparsed = parse(&packet.encode().unwrap()).unwrap();
+        assert_eq!(hex, compact(&reparsed));
+    }
+
+    #[test]
+    fn test_errors() {
+        let packet = Packet {
+            version: 0,
+            payload: Payload::Operator(
+                Operator::Sum,
+                vec![Packet {
+                    version: 9,
+                    payload: Payload::Literal(BigUint::from(1u64)),
+                }],
+            ),
+        };
+        let error = packet.encode().unwrap_err();
+        assert_eq!(
+            EncodeError {
+                kind: EncodeErrorKind::VersionTooLarge(9),
+                path: vec![0],
+            },
+            error
+        );
+        assert_eq!(
+            "version 9 doesn't fit in 3 bits at packet [0]",
+            error.to_string()
+        );
+
+        let packet = Packet {
+            version: 0,
+            payload: Payload::Operator(
+                Operator::Sum,
+                (0..3000)
+                    .map(|_| Packet {
+                        version: 0,
+                        payload: Payload::Literal(BigUint::from(1u64)),
+                    })
+                    .collect(),
+            ),
+        };
+        assert_eq!(
+            Err(EncodeError {
+                kind: EncodeErrorKind::TooManySubpackets(3000, 33_000),
+                path: vec![],
+            }),
+            packet
+                .to_bits(Encoding::Compact)
+                .map(|writer| writer.length)
+        );
+    }
+}
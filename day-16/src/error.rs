@@ -0,0 +1,58 @@
+use crate::operator::Operator;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DecodeErrorKind {
+    Truncated,
+    InvalidHexDigit(char),
+    InvalidBit(char),
+    LengthMismatch,
+    UnknownTypeId(u64),
+    WrongArity(Operator, usize),
+    NonZeroPadding,
+    ExcessPadding(usize),
+}
+
+// what went wrong while decoding and the bit offset into the transmission it went wrong at
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub offset: usize,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError { kind, offset }
+    }
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeErrorKind::Truncated => write!(f, "transmission ended unexpectedly"),
+            DecodeErrorKind::InvalidHexDigit(character) => {
+                write!(f, "invalid hexadecimal digit '{}'", character)
+            }
+            DecodeErrorKind::InvalidBit(character) => write!(f, "invalid bit '{}'", character),
+            DecodeErrorKind::LengthMismatch => {
+                write!(f, "sub-packets overran their declared length")
+            }
+            DecodeErrorKind::UnknownTypeId(type_id) => write!(f, "unknown type id {}", type_id),
+            DecodeErrorKind::WrongArity(operator, count) => {
+                write!(f, "{:?} can't take {} sub-packets", operator, count)
+            }
+            DecodeErrorKind::NonZeroPadding => write!(f, "padding bit set"),
+            DecodeErrorKind::ExcessPadding(length) => {
+                write!(f, "{} bits of padding, more than fills out a byte", length)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at bit {}", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,276 @@
+use crate::bignum::BigUint;
+use crate::operator::Operator;
+use crate::{Packet, Payload};
+use std::fmt;
+
+// the numeric type a transmission is evaluated in, u64 matches apply while the
+// wider types are opt in for transmissions whose values outgrow 64 bits
+pub trait Value: Clone + Ord + From<u64> {
+    fn from_literal(literal: &BigUint) -> Option<Self>;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+impl Value for u64 {
+    fn from_literal(literal: &BigUint) -> Option<Self> {
+        literal.to_u64()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *other)
+    }
+}
+
+impl Value for u128 {
+    fn from_literal(literal: &BigUint) -> Option<Self> {
+        literal.to_u128()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u128::checked_mul(*self, *other)
+    }
+}
+
+impl Value for BigUint {
+    fn from_literal(literal: &BigUint) -> Option<Self> {
+        Some(literal.clone())
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EvalErrorKind {
+    Overflow(Operator),
+    LiteralTooLarge,
+    // only possible for packets built by hand, the decoder won't produce them
+    WrongArity(Operator, usize),
+}
+
+// path holds the index of each sub-packet taken from the outermost packet down
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EvalError {
+    pub kind: EvalErrorKind,
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            EvalErrorKind::Overflow(operator) => write!(f, "{:?} overflowed", operator)?,
+            EvalErrorKind::LiteralTooLarge => write!(f, "literal too large")?,
+            EvalErrorKind::WrongArity(operator, count) => {
+                write!(f, "{:?} can't take {} sub-packets", operator, count)?
+            }
+        }
+        write!(f, " at packet {:?}", self.path)
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl Operator {
+    // None when the result doesn't fit, values must already have been checked with accepts
+    pub fn try_evaluate<V: Value>(&self, values: &[V]) -> Option<V> {
+        match self {
+            Operator::Sum => values
+                .iter()
+                .try_fold(V::from(0), |total, value| total.checked_add(value)),
+            Operator::Product => values
+                .iter()
+                .try_fold(V::from(1), |total, value| total.checked_mul(value)),
+            Operator::Minimum => values.iter().min().cloned(),
+            Operator::Maximum => values.iter().max().cloned(),
+            Operator::GreaterThan => Some(V::from((values[0] > values[1]) as u64)),
+            Operator::LessThan => Some(V::from((values[0] < values[1]) as u64)),
+            Operator::EqualTo => Some(V::from((values[0] == values[1]) as u64)),
+        }
+    }
+
+    // try_evaluate with the arity checked first, path is where the operator sits for errors
+    pub(crate) fn evaluate_at<V: Value>(
+        &self,
+        values: &[V],
+        path: &[usize],
+    ) -> Result<V, EvalError> {
+        let error = |kind| EvalError {
+            kind,
+            path: path.to_vec(),
+        };
+        if !self.accepts(values.len()) {
+            return Err(error(EvalErrorKind::WrongArity(*self, values.len())));
+        }
+        self.try_evaluate(values)
+            .ok_or_else(|| error(EvalErrorKind::Overflow(*self)))
+    }
+}
+
+impl Packet {
+    pub fn apply_checked(&self) -> Result<u64, EvalError> {
+        self.try_apply()
+    }
+
+    pub fn try_apply<V: Value>(&self) -> Result<V, EvalError> {
+        self.try_fold(|packet, path, values| match &packet.payload {
+            Payload::Literal(value) => V::from_literal(value).ok_or_else(|| EvalError {
+                kind: EvalErrorKind::LiteralTooLarge,
+                path: path.to_vec(),
+            }),
+            Payload::Operator(operator, _) => operator.evaluate_at(&values, path),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::EXAMPLES;
+    use crate::parse;
+
+    fn literal(value: u64) -> Packet {
+        Packet {
+            version: 0,
+            payload: Payload::Literal(BigUint::from(value)),
+        }
+    }
+
+    fn operator(operator: Operator, packets: Vec<Packet>) -> Packet {
+        Packet {
+            version: 0,
+            payload: Payload::Operator(operator, packets),
+        }
+    }
+
+    #[test]
+    fn test_checked_matches_apply() {
+        for example in EXAMPLES {
+            let packet = parse(example).unwrap();
+            assert_eq!(Ok(packet.apply()), packet.apply_checked());
+            assert_eq!(Ok(packet.apply() as u128), packet.try_apply::<u128>());
+        }
+    }
+
+    #[test]
+    fn test_overflow_path() {
+        let packet = operator(
+            Operator::Maximum,
+            vec![
+                literal(1),
+                operator(
+                    Operator::Sum,
+                    vec![
+                        literal(2),
+                        operator(Operator::Product, vec![literal(1 << 40), literal(1 << 40)]),
+                    ],
+                ),
+            ],
+        );
+
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::Overflow(Operator::Product),
+                path: vec![1, 1],
+            }),
+            packet.apply_checked()
+        );
+        assert_eq!(Ok((1 << 80) + 2), packet.try_apply::<u128>());
+    }
+
+    #[test]
+    fn test_sum_overflow() {
+        let packet = operator(Operator::Sum, vec![literal(u64::MAX), literal(1)]);
+        let error = packet.apply_checked().unwrap_err();
+        assert_eq!(EvalErrorKind::Overflow(Operator::Sum), error.kind);
+        assert_eq!("Sum overflowed at packet []", error.to_string());
+        assert_eq!(Ok(1 << 64), packet.try_apply::<u128>());
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        let packet = operator(Operator::LessThan, vec![literal(1)]);
+        let error = packet.apply_checked().unwrap_err();
+        assert_eq!(EvalErrorKind::WrongArity(Operator::LessThan, 1), error.kind);
+        assert_eq!(
+            "LessThan can't take 1 sub-packets at packet []",
+            error.to_string()
+        );
+
+        let packet = operator(Operator::Sum, vec![operator(Operator::Minimum, vec![])]);
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::WrongArity(Operator::Minimum, 0),
+                path: vec![0],
+            }),
+            packet.try_apply::<u128>()
+        );
+    }
+
+    #[test]
+    fn test_try_evaluate() {
+        assert_eq!(Some(6u64), Operator::Sum.try_evaluate(&[1, 2, 3]));
+        assert_eq!(Some(6u64), Operator::Product.try_evaluate(&[1, 2, 3]));
+        assert_eq!(Some(1u64), Operator::Minimum.try_evaluate(&[3, 1, 2]));
+        assert_eq!(Some(3u64), Operator::Maximum.try_evaluate(&[3, 1, 2]));
+        assert_eq!(Some(1u64), Operator::GreaterThan.try_evaluate(&[5, 2]));
+        assert_eq!(Some(0u64), Operator::LessThan.try_evaluate(&[5, 2]));
+        assert_eq!(Some(1u64), Operator::EqualTo.try_evaluate(&[2, 2]));
+        assert_eq!(None, Operator::Product.try_evaluate(&[u64::MAX, 2]));
+    }
+
+    #[test]
+    fn test_arbitrary_precision() {
+        let packet = operator(
+            Operator::Product,
+            vec![literal(u64::MAX), literal(u64::MAX), literal(u64::MAX)],
+        );
+        assert_eq!(
+            "6277101735386680762814942322444851025767571854389858533375",
+            packet.try_apply::<BigUint>().unwrap().to_string()
+        );
+        assert_eq!(
+            EvalErrorKind::Overflow(Operator::Product),
+            packet.try_apply::<u128>().unwrap_err().kind
+        );
+    }
+
+    #[test]
+    fn test_literal_too_large() {
+        let mut value = BigUint::default();
+        for _ in 0..20 {
+            value.push_nibble(0xF);
+        }
+        let packet = operator(
+            Operator::Maximum,
+            vec![
+                literal(1),
+                Packet {
+                    version: 0,
+                    payload: Payload::Literal(value.clone()),
+                },
+            ],
+        );
+
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::LiteralTooLarge,
+                path: vec![1],
+            }),
+            packet.apply_checked()
+        );
+        assert_eq!(Ok(value), packet.try_apply::<BigUint>());
+    }
+}
@@ -0,0 +1,418 @@
+use crate::bignum::BigUint;
+use crate::encoder::{EncodeError, MAX_VERSION};
+use crate::operator::Operator;
+use crate::{Packet, Payload};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ExpressionErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    UnknownFunction(String),
+    // a version handed out for a packet that doesn't fit in its 3 bit header field
+    InvalidVersion(u64),
+    // an expression too wide to write out, like a sum of thousands of large numbers
+    Encode(EncodeError),
+}
+
+// offset is the character position in the source the problem was found at, versions and
+// encoding don't come from the source so they're always reported at 0
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExpressionError {
+    pub kind: ExpressionErrorKind,
+    pub offset: usize,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExpressionErrorKind::UnexpectedCharacter(character) => {
+                write!(f, "unexpected character '{}'", character)?
+            }
+            ExpressionErrorKind::UnexpectedToken(token) => write!(f, "unexpected '{}'", token)?,
+            ExpressionErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression")?,
+            ExpressionErrorKind::UnknownFunction(name) => write!(f, "unknown function '{}'", name)?,
+            ExpressionErrorKind::InvalidVersion(version) => {
+                return write!(f, "version {} is larger than {}", version, MAX_VERSION)
+            }
+            ExpressionErrorKind::Encode(error) => return write!(f, "{}", error),
+        }
+        write!(f, " at {}", self.offset)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    Number(BigUint),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 8] = ["==", "+", "*", ">", "<", "(", ")", ","];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < characters.len() {
+        let start = position;
+        let character = characters[position];
+        let take_while = |position: &mut usize, test: fn(&char) -> bool| {
+            while *position < characters.len() && test(&characters[*position]) {
+                *position += 1;
+            }
+            characters[start..*position].iter().collect::<String>()
+        };
+
+        if character.is_whitespace() {
+            position += 1;
+        } else if character.is_ascii_digit() {
+            let digits = take_while(&mut position, char::is_ascii_digit);
+            tokens.push((Token::Number(digits.parse().unwrap()), start));
+        } else if character.is_ascii_alphabetic() {
+            let name = take_while(&mut position, char::is_ascii_alphanumeric);
+            tokens.push((Token::Name(name), start));
+        } else {
+            let rest: String = characters[position..].iter().take(2).collect();
+            match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                Some(symbol) => {
+                    position += symbol.len();
+                    tokens.push((Token::Symbol(symbol), start));
+                }
+                None => {
+                    return Err(ExpressionError {
+                        kind: ExpressionErrorKind::UnexpectedCharacter(character),
+                        offset: start,
+                    })
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// recursive descent over the tokens, loosest binding first:
+//   comparison := sum (('>' | '<' | '==') sum)*
+//   sum        := product ('+' product)*
+//   product    := atom ('*' atom)*
+//   atom       := number | name '(' comparison (',' comparison)* ')' | '(' comparison ')'
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some((_, offset)) => *offset,
+            None => self.end,
+        }
+    }
+
+    fn error(&self, kind: ExpressionErrorKind) -> ExpressionError {
+        ExpressionError {
+            kind,
+            offset: self.offset(),
+        }
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        match self.peek() {
+            Some(token) => self.error(ExpressionErrorKind::UnexpectedToken(token.to_string())),
+            None => self.error(ExpressionErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), ExpressionError> {
+        match self.peek() {
+            Some(Token::Symbol(found)) if *found == symbol => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Packet, ExpressionError> {
+        let mut left = self.sum()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Symbol(">")) => Operator::GreaterThan,
+                Some(Token::Symbol("<")) => Operator::LessThan,
+                Some(Token::Symbol("==")) => Operator::EqualTo,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            left = operator_packet(operator, vec![left, self.sum()?]);
+        }
+    }
+
+    fn sum(&mut self) -> Result<Packet, ExpressionError> {
+        self.chain("+", Operator::Sum, Parser::product)
+    }
+
+    fn product(&mut self) -> Result<Packet, ExpressionError> {
+        self.chain("*", Operator::Product, Parser::atom)
+    }
+
+    // a + b + c becomes a single sum with three sub-packets rather than nested sums
+    fn chain(
+        &mut self,
+        symbol: &'static str,
+        operator: Operator,
+        operand: fn(&mut Parser) -> Result<Packet, ExpressionError>,
+    ) -> Result<Packet, ExpressionError> {
+        let mut operands = vec![operand(self)?];
+        while self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            operands.push(operand(self)?);
+        }
+
+        match operands.len() {
+            1 => Ok(operands.pop().unwrap()),
+            _ => Ok(operator_packet(operator, operands)),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Packet, ExpressionError> {
+        let offset = self.offset();
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Packet {
+                version: 0,
+                payload: Payload::Literal(value),
+            }),
+            Some(Token::Symbol("(")) => {
+                let packet = self.comparison()?;
+                self.expect(")")?;
+                Ok(packet)
+            }
+            Some(Token::Name(name)) => {
+                let operator = match name.as_str() {
+                    "sum" => Operator::Sum,
+                    "product" => Operator::Product,
+                    "min" => Operator::Minimum,
+                    "max" => Operator::Maximum,
+                    _ => {
+                        return Err(ExpressionError {
+                            kind: ExpressionErrorKind::UnknownFunction(name),
+                            offset,
+                        })
+                    }
+                };
+
+                self.expect("(")?;
+                let mut arguments = vec![self.comparison()?];
+                while self.peek() == Some(&Token::Symbol(",")) {
+                    self.position += 1;
+                    arguments.push(self.comparison()?);
+                }
+                self.expect(")")?;
+                Ok(operator_packet(operator, arguments))
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+fn operator_packet(operator: Operator, packets: Vec<Packet>) -> Packet {
+    Packet {
+        version: 0,
+        payload: Payload::Operator(operator, packets),
+    }
+}
+
+// hands out versions to every packet in pre-order, outermost packet first
+fn assign_versions(
+    packet: &mut Packet,
+    versions: &mut dyn FnMut() -> u64,
+) -> Result<(), ExpressionError> {
+    packet.version = versions();
+    if packet.version > MAX_VERSION {
+        return Err(ExpressionError {
+            kind: ExpressionErrorKind::InvalidVersion(packet.version),
+            offset: 0,
+        });
+    }
+
+    if let Payload::Operator(_, packets) = &mut packet.payload {
+        for packet in packets {
+            assign_versions(packet, versions)?;
+        }
+    }
+    Ok(())
+}
+
+// builds the packet tree for an expression like `min(3, 4*5) + (7 > 2)`, versions
+// is called once per packet in pre-order and anything above 7 is an error
+pub fn to_packet(
+    source: &str,
+    mut versions: impl FnMut() -> u64,
+) -> Result<Packet, ExpressionError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        end: source.chars().count(),
+    };
+
+    let mut packet = parser.comparison()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
+    }
+
+    assign_versions(&mut packet, &mut versions)?;
+    Ok(packet)
+}
+
+// compiles an expression straight to a hex transmission with every packet at version
+pub fn compile(source: &str, version: u64) -> Result<String, ExpressionError> {
+    to_packet(source, || version)?
+        .encode()
+        .map_err(|error| ExpressionError {
+            kind: ExpressionErrorKind::Encode(error),
+            offset: 0,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn sexpr(source: &str) -> String {
+        to_packet(source, || 0).unwrap().sexpr().to_string()
+    }
+
+    fn error(source: &str) -> (ExpressionErrorKind, usize) {
+        let error = to_packet(source, || 0).unwrap_err();
+        (error.kind, error.offset)
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!("(+ 1 (* 2 3))", sexpr("1 + 2 * 3"));
+        assert_eq!("(* (+ 1 2) 3)", sexpr("(1 + 2) * 3"));
+        assert_eq!("(+ 1 2 3)", sexpr("1+2+3"));
+        assert_eq!("(== (+ 1 3) (* 2 2))", sexpr("1 + 3 == 2 * 2"));
+        assert_eq!("(< (> 1 2) 3)", sexpr("1 > 2 < 3"));
+        assert_eq!("7", sexpr("((7))"));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            "(+ (min 3 (* 4 5)) (> 7 2))",
+            sexpr("min(3, 4*5) + (7 > 2)")
+        );
+        assert_eq!("(max 1 2 3)", sexpr("max(1, 2, 3)"));
+        assert_eq!("(* 2 3)", sexpr("product(2, 3)"));
+        assert_eq!("(+ 5)", sexpr("sum(5)"));
+    }
+
+    #[test]
+    fn test_compile() {
+        let hex = compile("min(3, 4*5) + (7 > 2)", 1).unwrap();
+        let packet = parse(&hex).unwrap();
+        assert_eq!(4, packet.apply());
+        assert_eq!(9, packet.version_sum());
+
+        let hex = compile("123456789012345678901234567890 * 2", 0).unwrap();
+        assert_eq!(
+            "(* 123456789012345678901234567890 2)",
+            parse(&hex).unwrap().sexpr().to_string()
+        );
+    }
+
+    #[test]
+    fn test_versions() {
+        let mut next = 0;
+        let packet = to_packet("max(1, 2 * 3)", || {
+            next += 1;
+            next
+        })
+        .unwrap();
+
+        assert_eq!(
+            "v1 maximum\n  v2 literal 1\n  v3 product\n    v4 literal 2\n    v5 literal 3",
+            packet.to_string()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            (ExpressionErrorKind::UnexpectedCharacter('-'), 2),
+            error("1 - 2")
+        );
+        assert_eq!((ExpressionErrorKind::UnexpectedEnd, 3), error("1 +"));
+        assert_eq!(
+            (ExpressionErrorKind::UnexpectedToken(")".to_string()), 1),
+            error("1)")
+        );
+        assert_eq!(
+            (ExpressionErrorKind::UnknownFunction("avg".to_string()), 0),
+            error("avg(1, 2)")
+        );
+        assert_eq!(
+            (ExpressionErrorKind::UnexpectedToken(")".to_string()), 4),
+            error("min()")
+        );
+        assert_eq!((ExpressionErrorKind::UnexpectedEnd, 6), error("(1 + 2"));
+        assert_eq!(
+            "unexpected end of expression at 3",
+            to_packet("1 +", || 0).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        let error = compile("1", 9).unwrap_err();
+        assert_eq!(ExpressionErrorKind::InvalidVersion(9), error.kind);
+        assert_eq!("version 9 is larger than 7", error.to_string());
+
+        let mut next = 5;
+        let error = to_packet("1 + 2 + 3", || {
+            next += 1;
+            next
+        })
+        .unwrap_err();
+        assert_eq!(ExpressionErrorKind::InvalidVersion(8), error.kind);
+    }
+
+    #[test]
+    fn test_unencodable() {
+        let source = format!("sum({})", vec!["1"; 3000].join(", "));
+        assert_eq!(
+            "3000 sub-packets over 33000 bits can't be counted or measured at packet []",
+            compile(&source, 0).unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use crate::encoder::BitWriter;
+use crate::operator::Operator;
+use crate::LITERAL_TYPE_ID;
+
+// every example transmission from the puzzle, covering literals and both length types
+pub const EXAMPLES: [&str; 15] = [
+    "D2FE28",
+    "38006F45291200",
+    "EE00D40C823060",
+    "8A004A801A8002F478",
+    "620080001611562C8802118E34",
+    "C0015000016115A2E0802F182340",
+    "A0016C880162017C3686B18A3D4780",
+    "C200B40A82",
+    "04005AC33890",
+    "880086C3E88112",
+    "CE00C43D881120",
+    "D8005AC2A8F0",
+    "F600BC2D8F",
+    "9C005AC2F8F0",
+    "9C0141080250320F1802104A08",
+];
+
+pub const INPUT: &str = include_str!("input.txt");
+
+// every level is a version 1 maximum counting a single sub-packet, wrapping a literal 7,
+// deep enough at the sizes used to overflow the stack of anything that recurses
+pub fn deep_transmission(depth: usize) -> String {
+    let mut writer = BitWriter::new();
+    for _ in 0..depth {
+        writer.write(1, 3);
+        writer.write(Operator::Maximum.type_id(), 3);
+        writer.write(1, 1);
+        writer.write(1, 11);
+    }
+    writer.write(0, 3);
+    writer.write(LITERAL_TYPE_ID, 3);
+    writer.write(0b00111, 5);
+    writer.to_hex()
+}
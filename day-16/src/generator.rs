@@ -0,0 +1,221 @@
+use crate::bignum::BigUint;
+use crate::operator::Operator;
+use crate::{Packet, Payload};
+
+// xorshift64, small and good enough for shaping test data without pulling in a crate
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        // scramble the seed so nearby seeds start far apart, xorshift can't start at zero
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Random((state ^ (state >> 31)).max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // a value in 0..bound
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+// builds random packet trees that always encode and decode, operators nest at most
+// max_depth deep, variadic operators get 1 to max_fan_out sub-packets and literals up to
+// max_literal_nibbles 4 bit groups
+pub struct Generator {
+    random: Random,
+    pub max_depth: usize,
+    pub max_fan_out: usize,
+    pub max_literal_nibbles: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            random: Random::new(seed),
+            max_depth: 5,
+            max_fan_out: 4,
+            max_literal_nibbles: 24,
+        }
+    }
+
+    pub fn packet(&mut self) -> Packet {
+        self.packet_at(self.max_depth)
+    }
+
+    fn packet_at(&mut self, depth: usize) -> Packet {
+        let version = self.random.below(8);
+        if depth == 0 || self.random.below(3) == 0 {
+            let mut value = BigUint::default();
+            for _ in 0..self.random.below(self.max_literal_nibbles as u64 + 1) {
+                value.push_nibble(self.random.below(16) as u8);
+            }
+            return Packet {
+                version,
+                payload: Payload::Literal(value),
+            };
+        }
+
+        let operator = Operator::ALL[self.random.below(7) as usize];
+        let count = match operator.is_comparison() {
+            true => 2,
+            false => 1 + self.random.below(self.max_fan_out.max(1) as u64),
+        };
+        Packet {
+            version,
+            payload: Payload::Operator(
+                operator,
+                (0..count).map(|_| self.packet_at(depth - 1)).collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::{disassemble, FieldKind};
+    use crate::eval::EvalErrorKind;
+    use crate::{parse, parse_strict};
+
+    const CASES: u64 = 300;
+
+    // evaluates without anything from eval, noting whether any step outgrew a u64, literals
+    // are kept small enough by the tests that only sums and products can do that
+    fn reference(packet: &Packet, overflowed: &mut bool) -> BigUint {
+        let value = match &packet.payload {
+            Payload::Literal(value) => value.clone(),
+            Payload::Operator(operator, packets) => {
+                let values: Vec<BigUint> = packets
+                    .iter()
+                    .map(|packet| reference(packet, overflowed))
+                    .collect();
+                let truth = |test: bool| BigUint::from(test as u64);
+                // apply works left to right, so every running total has to fit as well
+                let mut running = |start: u64, step: fn(&BigUint, &BigUint) -> BigUint| {
+                    values.iter().fold(BigUint::from(start), |total, value| {
+                        let total = step(&total, value);
+                        *overflowed |= total.to_u64().is_none();
+                        total
+                    })
+                };
+                match operator {
+                    Operator::Sum => running(0, |a, b| a + b),
+                    Operator::Product => running(1, |a, b| a * b),
+                    Operator::Minimum => values.iter().min().unwrap().clone(),
+                    Operator::Maximum => values.iter().max().unwrap().clone(),
+                    Operator::GreaterThan => truth(values[0] > values[1]),
+                    Operator::LessThan => truth(values[0] < values[1]),
+                    Operator::EqualTo => truth(values[0] == values[1]),
+                }
+            }
+        };
+
+        *overflowed |= value.to_u64().is_none();
+        value
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let first = Generator::new(16).packet();
+        assert_eq!(first, Generator::new(16).packet());
+        assert_ne!(first, Generator::new(17).packet());
+    }
+
+    #[test]
+    fn test_shape_limits() {
+        let mut generator = Generator::new(2021);
+        generator.max_depth = 3;
+        generator.max_fan_out = 2;
+        generator.max_literal_nibbles = 1;
+
+        for _ in 0..CASES {
+            let packet = generator.packet();
+            let depth = packet.fold(|_, depths| 1 + depths.into_iter().max().unwrap_or(0));
+            assert!(depth <= 4);
+            packet.fold(|packet, _: Vec<()>| match &packet.payload {
+                Payload::Literal(value) => assert!(value.bits() <= 4),
+                Payload::Operator(operator, packets) => {
+                    assert!(operator.accepts(packets.len()));
+                    assert!(packets.len() <= 2);
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_version_sum_property() {
+        let mut generator = Generator::new(1);
+        for _ in 0..CASES {
+            let packet = generator.packet();
+            let hex = packet.encode().unwrap();
+            // read straight off the bits rather than out of the tree
+            let versions: u64 = disassemble(&hex)
+                .fields
+                .iter()
+                .filter(|field| field.kind == FieldKind::Version)
+                .map(|field| field.value)
+                .sum();
+            assert_eq!(versions, packet.version_sum(), "{}", hex);
+            assert_eq!(versions, parse(&hex).unwrap().version_sum(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn test_decoding_never_panics() {
+        let mut generator = Generator::new(2);
+        let mut random = Random::new(3);
+        for _ in 0..CASES {
+            let hex = generator.packet().encode().unwrap();
+            assert!(parse_strict(&hex).is_ok(), "{}", hex);
+
+            // cut it short and flip a digit, the decoder has to cope either way
+            let cut = random.below(hex.len() as u64) as usize;
+            let _ = parse(&hex[..cut]);
+            let mut digits: Vec<char> = hex.chars().collect();
+            let index = random.below(digits.len() as u64) as usize;
+            let flipped = digits[index].to_digit(16).unwrap() ^ (1 << random.below(4));
+            digits[index] = std::char::from_digit(flipped, 16).unwrap();
+            if let Ok(packet) = parse(&digits.into_iter().collect::<String>()) {
+                let _ = packet.apply_checked();
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_property() {
+        let mut generator = Generator::new(4);
+        generator.max_literal_nibbles = 6;
+        let (mut fits, mut overflows) = (0, 0);
+
+        for _ in 0..CASES {
+            let packet = generator.packet();
+            let mut overflowed = false;
+            let expected = reference(&packet, &mut overflowed);
+
+            assert_eq!(Ok(&expected), packet.try_apply::<BigUint>().as_ref());
+            match packet.apply_checked() {
+                Ok(value) => {
+                    assert!(!overflowed);
+                    assert_eq!(expected.to_u64(), Some(value));
+                    fits += 1;
+                }
+                Err(error) => {
+                    assert!(overflowed);
+                    assert!(matches!(error.kind, EvalErrorKind::Overflow(_)));
+                    overflows += 1;
+                }
+            }
+        }
+        // make sure the generator is actually exercising both outcomes
+        assert!(fits > 0 && overflows > 0, "{} {}", fits, overflows);
+    }
+}
@@ -0,0 +1,579 @@
+use bignum::BigUint;
+use disassembler::FieldKind;
+use error::{DecodeError, DecodeErrorKind};
+use operator::Operator;
+use reader::{HexReader, Input};
+
+pub mod batch;
+pub mod bignum;
+pub mod bytecode;
+pub mod diff;
+pub mod disassembler;
+pub mod encoder;
+pub mod error;
+pub mod eval;
+pub mod expression;
+#[cfg(test)]
+mod fixtures;
+pub mod generator;
+pub mod operator;
+pub mod optimizer;
+pub mod printer;
+pub mod reader;
+pub mod registry;
+pub mod stats;
+pub mod trace;
+pub mod view;
+pub mod visitor;
+
+pub const LITERAL_TYPE_ID: u64 = 4;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub version: u64,
+    pub payload: Payload,
+}
+#[derive(Debug, PartialEq, Eq)]
+pub enum Payload {
+    Literal(BigUint),
+    Operator(Operator, Vec<Packet>),
+}
+
+impl Packet {
+    pub fn type_id(&self) -> u64 {
+        match &self.payload {
+            Payload::Literal(_) => LITERAL_TYPE_ID,
+            Payload::Operator(operator, _) => operator.type_id(),
+        }
+    }
+
+    pub fn version_sum(&self) -> u64 {
+        self.fold(|packet, sums| packet.version + sums.iter().sum::<u64>())
+    }
+
+    // evaluates in u64, panicking if anything doesn't fit, see try_apply for the alternatives
+    pub fn apply(&self) -> u64 {
+        match self.apply_checked() {
+            Ok(value) => value,
+            Err(error) => panic!("unable to evaluate packet: {}", error),
+        }
+    }
+}
+
+// dropping a deeply nested packet recursively would overflow the stack, so sub-packets
+// are moved onto a heap allocated list and dropped one at a time instead
+impl Drop for Packet {
+    fn drop(&mut self) {
+        if let Payload::Operator(_, packets) = &mut self.payload {
+            let mut pending = std::mem::take(packets);
+            while let Some(mut packet) = pending.pop() {
+                if let Payload::Operator(_, packets) = &mut packet.payload {
+                    pending.append(packets);
+                }
+            }
+        }
+    }
+}
+
+// how an operator says where its sub-packets stop, shared with the lazy view
+pub(crate) enum Ending {
+    Position(usize),
+    Count(u64),
+}
+
+// an operator whose sub-packets are still being read
+struct Frame {
+    start: usize,
+    version: u64,
+    operator: Operator,
+    ending: Ending,
+    subpackets: Vec<Packet>,
+}
+
+impl Frame {
+    fn is_complete(&self, position: usize) -> bool {
+        match self.ending {
+            Ending::Position(ending) => position >= ending,
+            Ending::Count(count) => self.subpackets.len() as u64 == count,
+        }
+    }
+
+    fn finish(self) -> Result<Packet, DecodeError> {
+        if !self.operator.accepts(self.subpackets.len()) {
+            return Err(DecodeError::new(
+                DecodeErrorKind::WrongArity(self.operator, self.subpackets.len()),
+                self.start,
+            ));
+        }
+
+        Ok(Packet {
+            version: self.version,
+            payload: Payload::Operator(self.operator, self.subpackets),
+        })
+    }
+}
+
+// a packet header decoded in place, for a literal that's the whole packet
+pub(crate) struct Header {
+    pub(crate) version: u64,
+    pub(crate) body: Body,
+    // just past the literal's last group or the operator's length field
+    pub(crate) end: usize,
+}
+
+pub(crate) enum Body {
+    Literal(BigUint),
+    Operator(Operator, Ending),
+}
+
+// decodes the header at offset without moving the reader, shared by the parser and the lazy view
+pub(crate) fn read_header(reader: &HexReader, offset: usize) -> Result<Header, DecodeError> {
+    read_header_fields(reader, offset, |_, _, _, _| ())
+}
+
+// read_header that also hands over each field as it's read, given its kind, offset, width and
+// value, so the disassembler sees every field up to wherever a broken header stops
+pub(crate) fn read_header_fields(
+    reader: &HexReader,
+    offset: usize,
+    mut field: impl FnMut(FieldKind, usize, usize, u64),
+) -> Result<Header, DecodeError> {
+    let mut read = |kind, position, width| {
+        let value = reader.read_at(position, width)?;
+        field(kind, position, width, value);
+        Ok::<u64, DecodeError>(value)
+    };
+
+    let version = read(FieldKind::Version, offset, 3)?;
+    let type_id = read(FieldKind::TypeId, offset + 3, 3)?;
+    let mut position = offset + 6;
+
+    if type_id == LITERAL_TYPE_ID {
+        let mut value = BigUint::default();
+        loop {
+            let group = read(FieldKind::LiteralGroup, position, 5)?;
+            value.push_nibble((group & 0xF) as u8);
+            position += 5;
+            if group & 0b10000 == 0 {
+                return Ok(Header {
+                    version,
+                    body: Body::Literal(value),
+                    end: position,
+                });
+            }
+        }
+    }
+
+    let operator = match Operator::from_type_id(type_id) {
+        Some(operator) => operator,
+        None => {
+            return Err(DecodeError::new(
+                DecodeErrorKind::UnknownTypeId(type_id),
+                offset + 3,
+            ))
+        }
+    };
+
+    let (ending, end) = match read(FieldKind::LengthType, position, 1)? {
+        0 => {
+            let subpacket_length = read(FieldKind::BitLength, position + 1, 15)? as usize;
+            (
+                Ending::Position(position + 16 + subpacket_length),
+                position + 16,
+            )
+        }
+        _ => (
+            Ending::Count(read(FieldKind::PacketCount, position + 1, 11)?),
+            position + 12,
+        ),
+    };
+
+    Ok(Header {
+        version,
+        body: Body::Operator(operator, ending),
+        end,
+    })
+}
+
+// reads a packet header, returning the literal packet or the operator still needing sub-packets
+fn parse_header(reader: &mut HexReader) -> Result<Result<Packet, Frame>, DecodeError> {
+    let start = reader.position;
+    let header = read_header(reader, start)?;
+    reader.position = header.end;
+
+    match header.body {
+        Body::Literal(value) => Ok(Ok(Packet {
+            version: header.version,
+            payload: Payload::Literal(value),
+        })),
+        Body::Operator(operator, ending) => Ok(Err(Frame {
+            start,
+            version: header.version,
+            operator,
+            ending,
+            subpackets: Vec::new(),
+        })),
+    }
+}
+
+// walks the transmission with an explicit stack of open operators rather than recursing,
+// so nesting depth is only limited by memory
+pub(crate) fn parse_packet(reader: &mut HexReader) -> Result<Packet, DecodeError> {
+    let mut open: Vec<Frame> = Vec::new();
+
+    loop {
+        let mut packet = match parse_header(reader)? {
+            Ok(packet) => packet,
+            Err(frame) if !frame.is_complete(reader.position) => {
+                open.push(frame);
+                continue;
+            }
+            Err(frame) => frame.finish()?,
+        };
+
+        // hand the finished packet to its parent, closing every parent it completes
+        loop {
+            let frame = match open.last_mut() {
+                Some(frame) => frame,
+                None => return Ok(packet),
+            };
+
+            frame.subpackets.push(packet);
+            if let Ending::Position(ending) = frame.ending {
+                if reader.position > ending {
+                    return Err(DecodeError::new(
+                        DecodeErrorKind::LengthMismatch,
+                        reader.position,
+                    ));
+                }
+            }
+
+            if !frame.is_complete(reader.position) {
+                break;
+            }
+            packet = open.pop().unwrap().finish()?;
+        }
+    }
+}
+
+// whatever follows the outermost packet is ignored, see parse_strict and parse_lenient
+pub fn parse(input: &str) -> Result<Packet, DecodeError> {
+    let mut reader = HexReader::new(input)?;
+    parse_packet(&mut reader)
+}
+
+// like parse for a transmission in whichever form the caller has it in
+pub fn parse_input(input: Input) -> Result<Packet, DecodeError> {
+    let mut reader = HexReader::from_input(input)?;
+    parse_packet(&mut reader)
+}
+
+// the bits left over after the outermost packet as a string of 0s and 1s, offset is where
+// the first of them is in the transmission
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Trailing {
+    pub offset: usize,
+    pub bits: String,
+}
+
+// only allows the transmission to end in the zero bits needed to fill out its last byte
+pub fn parse_strict(input: &str) -> Result<Packet, DecodeError> {
+    parse_input_strict(Input::Hex(input))
+}
+
+pub fn parse_input_strict(input: Input) -> Result<Packet, DecodeError> {
+    let (packet, trailing) = parse_input_lenient(input)?;
+    if let Some(index) = trailing.bits.find('1') {
+        return Err(DecodeError::new(
+            DecodeErrorKind::NonZeroPadding,
+            trailing.offset + index,
+        ));
+    }
+    if trailing.bits.len() >= 8 {
+        return Err(DecodeError::new(
+            DecodeErrorKind::ExcessPadding(trailing.bits.len()),
+            trailing.offset,
+        ));
+    }
+    Ok(packet)
+}
+
+// hands back whatever follows the outermost packet alongside it
+pub fn parse_lenient(input: &str) -> Result<(Packet, Trailing), DecodeError> {
+    parse_input_lenient(Input::Hex(input))
+}
+
+pub fn parse_input_lenient(input: Input) -> Result<(Packet, Trailing), DecodeError> {
+    let mut reader = HexReader::from_input(input)?;
+    let packet = parse_packet(&mut reader)?;
+
+    let offset = reader.position;
+    let mut bits = String::with_capacity(reader.remaining());
+    while reader.remaining() > 0 {
+        let width = reader.remaining().min(64);
+        bits.push_str(&format!("{:0width$b}", reader.read_decimal(width)?));
+    }
+    Ok((packet, Trailing { offset, bits }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{deep_transmission, INPUT};
+
+    fn assert_literal(packet: &Packet, version: u64, type_id: u64, value: u64) {
+        assert_eq!(version, packet.version);
+        assert_eq!(type_id, packet.type_id());
+        match packet.payload {
+            Payload::Literal(ref actual_value) => assert_eq!(Some(value), actual_value.to_u64()),
+            _ => panic!("unexpected payload"),
+        }
+    }
+
+    #[test]
+    fn test_literal_parsing() {
+        let mut reader = HexReader::new("D2FE28").unwrap();
+        let packet = parse_packet(&mut reader).unwrap();
+        assert_eq!(21, reader.position);
+        assert_literal(&packet, 6, 4, 2021);
+    }
+
+    #[test]
+    fn test_operator_fifeteen() {
+        let mut reader = HexReader::new("38006F45291200").unwrap();
+        let packet = parse_packet(&mut reader).unwrap();
+        assert_eq!(1, packet.version);
+        assert_eq!(6, packet.type_id());
+        match &packet.payload {
+            Payload::Operator(Operator::LessThan, values) => {
+                assert_eq!(2, values.len());
+                assert_literal(values.first().unwrap(), 6, 4, 10);
+                assert_literal(values.get(1).unwrap(), 2, 4, 20);
+            }
+            _ => panic!("unexpected payload"),
+        }
+    }
+
+    #[test]
+    fn test_operator_eleven() {
+        let mut reader = HexReader::new("EE00D40C823060").unwrap();
+        let packet = parse_packet(&mut reader).unwrap();
+
+        assert_eq!(7, packet.version);
+        assert_eq!(3, packet.type_id());
+        match &packet.payload {
+            Payload::Operator(Operator::Maximum, values) => {
+                assert_literal(values.first().unwrap(), 2, 4, 1);
+                assert_literal(values.get(1).unwrap(), 4, 4, 2);
+                assert_literal(values.get(2).unwrap(), 1, 4, 3);
+            }
+            _ => panic!("unexpected payload"),
+        }
+    }
+
+    #[test]
+    fn test_packet_construction() {
+        let a = Packet {
+            version: 1,
+            payload: Payload::Literal(BigUint::from(3u64)),
+        };
+
+        let b = Packet {
+            version: 3,
+            payload: Payload::Literal(BigUint::from(3u64)),
+        };
+
+        assert_eq!(3, b.version_sum());
+
+        let c = Packet {
+            version: 2,
+            payload: Payload::Operator(Operator::LessThan, vec![a, b]),
+        };
+
+        assert_eq!(6, c.version_sum());
+    }
+
+    #[test]
+    fn test_part_one_example_input() {
+        assert_eq!(16, parse("8A004A801A8002F478").unwrap().version_sum());
+        assert_eq!(
+            12,
+            parse("620080001611562C8802118E34").unwrap().version_sum()
+        );
+        assert_eq!(
+            23,
+            parse("C0015000016115A2E0802F182340").unwrap().version_sum()
+        );
+        assert_eq!(
+            31,
+            parse("A0016C880162017C3686B18A3D4780")
+                .unwrap()
+                .version_sum()
+        );
+    }
+
+    #[test]
+    fn test_part_two_example_input() {
+        assert_eq!(3, parse("C200B40A82").unwrap().apply());
+        assert_eq!(54, parse("04005AC33890").unwrap().apply());
+        assert_eq!(7, parse("880086C3E88112").unwrap().apply());
+        assert_eq!(9, parse("CE00C43D881120").unwrap().apply());
+        assert_eq!(1, parse("D8005AC2A8F0").unwrap().apply());
+        assert_eq!(0, parse("F600BC2D8F").unwrap().apply());
+        assert_eq!(0, parse("9C005AC2F8F0").unwrap().apply());
+        assert_eq!(1, parse("9C0141080250320F1802104A08").unwrap().apply());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = |kind, offset| Err(DecodeError::new(kind, offset));
+
+        assert_eq!(
+            error(DecodeErrorKind::InvalidHexDigit('x'), 12),
+            parse("D2Fx28").map(|p| p.version)
+        );
+        assert_eq!(
+            error(DecodeErrorKind::Truncated, 16),
+            parse("D2FE").map(|p| p.version)
+        );
+        assert_eq!(
+            error(DecodeErrorKind::Truncated, 0),
+            parse("").map(|p| p.version)
+        );
+        // an operator declaring 10 bits of sub-packets followed by an 11 bit literal
+        assert_eq!(
+            error(DecodeErrorKind::LengthMismatch, 33),
+            parse("200028408").map(|p| p.version)
+        );
+    }
+
+    #[test]
+    fn test_arity_errors() {
+        // a less than operator with a single literal sub-packet
+        assert_eq!(
+            Err(DecodeError::new(
+                DecodeErrorKind::WrongArity(Operator::LessThan, 1),
+                0
+            )),
+            parse("38002C408").map(|p| p.version)
+        );
+        // a minimum operator counting zero sub-packets
+        assert_eq!(
+            Err(DecodeError::new(
+                DecodeErrorKind::WrongArity(Operator::Minimum, 0),
+                0
+            )),
+            parse("0A000").map(|p| p.version)
+        );
+    }
+
+    #[test]
+    fn test_input_formats() {
+        let expected = parse("EE00D40C823060").unwrap();
+        for input in [
+            Input::Hex("0xee00_d40c_8230_60"),
+            Input::Bits("111 011 1 00000000011 01010000001 10010000010 00110000011 00000"),
+            Input::Bytes(&[0xEE, 0x00, 0xD4, 0x0C, 0x82, 0x30, 0x60]),
+        ] {
+            assert_eq!(Ok(&expected), parse_input(input).as_ref());
+        }
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::Truncated, 0)),
+            parse("0x").map(|p| p.version)
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        assert!(parse_strict("D2FE28").is_ok());
+        assert!(parse_strict("38006F45291200").is_ok());
+        assert!(parse_strict(INPUT).is_ok());
+
+        let error = |kind, offset| Err(DecodeError::new(kind, offset));
+        assert_eq!(
+            error(DecodeErrorKind::NonZeroPadding, 23),
+            parse_strict("D2FE29").map(|p| p.version)
+        );
+        assert_eq!(
+            error(DecodeErrorKind::ExcessPadding(11), 21),
+            parse_strict("D2FE2800").map(|p| p.version)
+        );
+        // a second packet tacked on the end is just more padding that isn't zero
+        assert_eq!(
+            error(DecodeErrorKind::NonZeroPadding, 24),
+            parse_strict("D2FE28D2FE28").map(|p| p.version)
+        );
+        assert_eq!(
+            "padding bit set at bit 23",
+            parse_strict("D2FE29").unwrap_err().to_string()
+        );
+
+        // a bit string needn't fill out a byte at all, but the bytes it's packed into do
+        assert!(parse_input_strict(Input::Bits("110100101111111000101")).is_ok());
+        assert!(parse_input_strict(Input::Bytes(&[0xD2, 0xFE, 0x28])).is_ok());
+        assert_eq!(
+            error(DecodeErrorKind::ExcessPadding(11), 21),
+            parse_input_strict(Input::Bytes(&[0xD2, 0xFE, 0x28, 0x00])).map(|p| p.version)
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let (packet, trailing) = parse_lenient("D2FE28").unwrap();
+        assert_literal(&packet, 6, 4, 2021);
+        assert_eq!(
+            Trailing {
+                offset: 21,
+                bits: "000".to_string()
+            },
+            trailing
+        );
+
+        let (_, trailing) = parse_lenient("D2FE29A").unwrap();
+        assert_eq!("0011010", trailing.bits);
+
+        let long = format!("D2FE28{}", "F".repeat(20));
+        let (_, trailing) = parse_lenient(&long).unwrap();
+        assert_eq!(format!("000{}", "1".repeat(80)), trailing.bits);
+
+        let (_, trailing) = parse_input_lenient(Input::Bits("110100101111111000101 01")).unwrap();
+        assert_eq!("01", trailing.bits);
+    }
+
+    #[test]
+    fn test_long_literal() {
+        // seventeen 4-bit groups of F, more than a u64 can hold
+        let packet = parse("13FFFFFFFFFFFFFFFFFFFDE").unwrap();
+        match packet.payload {
+            Payload::Literal(ref value) => {
+                assert_eq!(68, value.bits());
+                assert_eq!("295147905179352825855", value.to_string());
+            }
+            _ => panic!("unexpected payload"),
+        }
+        assert!(packet.apply_checked().is_err());
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let packet = parse(&deep_transmission(DEPTH)).unwrap();
+
+        let mut depth = 0;
+        let mut current = &packet;
+        while let Payload::Operator(Operator::Maximum, packets) = &current.payload {
+            depth += 1;
+            current = &packets[0];
+        }
+        assert_eq!(DEPTH, depth);
+        assert_eq!(DEPTH as u64, packet.version_sum());
+        assert_eq!(7, packet.apply());
+        assert_eq!(
+            Some(7),
+            match &current.payload {
+                Payload::Literal(value) => value.to_u64(),
+                _ => None,
+            }
+        );
+    }
+}
@@ -0,0 +1,79 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Operator {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 7] = [
+        Operator::Sum,
+        Operator::Product,
+        Operator::Minimum,
+        Operator::Maximum,
+        Operator::GreaterThan,
+        Operator::LessThan,
+        Operator::EqualTo,
+    ];
+
+    pub fn from_type_id(type_id: u64) -> Option<Operator> {
+        Operator::ALL
+            .into_iter()
+            .find(|operator| operator.type_id() == type_id)
+    }
+
+    pub fn type_id(&self) -> u64 {
+        match self {
+            Operator::Sum => 0,
+            Operator::Product => 1,
+            Operator::Minimum => 2,
+            Operator::Maximum => 3,
+            Operator::GreaterThan => 5,
+            Operator::LessThan => 6,
+            Operator::EqualTo => 7,
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::GreaterThan | Operator::LessThan | Operator::EqualTo
+        )
+    }
+
+    // comparisons need exactly two sub-packets, everything else at least one
+    pub fn accepts(&self, count: usize) -> bool {
+        match self.is_comparison() {
+            true => count == 2,
+            false => count >= 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_ids() {
+        for operator in Operator::ALL {
+            assert_eq!(Some(operator), Operator::from_type_id(operator.type_id()));
+        }
+        assert_eq!(None, Operator::from_type_id(4));
+        assert_eq!(None, Operator::from_type_id(8));
+    }
+
+    #[test]
+    fn test_arity() {
+        assert!(Operator::Sum.accepts(1));
+        assert!(Operator::Maximum.accepts(5));
+        assert!(!Operator::Minimum.accepts(0));
+        assert!(Operator::EqualTo.accepts(2));
+        assert!(!Operator::LessThan.accepts(1));
+        assert!(!Operator::GreaterThan.accepts(3));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::bignum::BigUint;
+use crate::encoder::{EncodeError, Encoding, MAX_SUBPACKET_COUNT};
+use crate::error::DecodeError;
+use crate::eval::EvalError;
+use crate::operator::Operator;
+use crate::reader::HexReader;
+use crate::{parse_packet, Packet, Payload};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OptimizeError {
+    Decode(DecodeError),
+    Eval(EvalError),
+    Encode(EncodeError),
+}
+
+impl From<DecodeError> for OptimizeError {
+    fn from(error: DecodeError) -> Self {
+        OptimizeError::Decode(error)
+    }
+}
+
+impl From<EvalError> for OptimizeError {
+    fn from(error: EvalError) -> Self {
+        OptimizeError::Eval(error)
+    }
+}
+
+impl From<EncodeError> for OptimizeError {
+    fn from(error: EncodeError) -> Self {
+        OptimizeError::Encode(error)
+    }
+}
+
+impl fmt::Display for OptimizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimizeError::Decode(error) => write!(f, "{}", error),
+            OptimizeError::Eval(error) => write!(f, "{}", error),
+            OptimizeError::Encode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for OptimizeError {}
+
+// the optimized packet doesn't keep every version, a sum or product merged into its parent
+// takes its version with it, as does every operator replaced by a literal, so version_sum
+// will generally come out lower than the original's
+pub struct Optimized {
+    pub packet: Packet,
+    pub value: BigUint,
+    // the original as encode would write it, against the optimized packet written compactly
+    pub original_bits: usize,
+    pub optimized_bits: usize,
+}
+
+impl Optimized {
+    pub fn encode(&self) -> Result<String, EncodeError> {
+        Ok(self.packet.to_bits(Encoding::Compact)?.to_hex())
+    }
+}
+
+// sums of sums and products of products are merged into their parent, and every operator
+// that fold says yes to (given the packet and its path) is replaced by a literal of its
+// value under the operator's version
+pub fn optimize(
+    packet: &Packet,
+    fold: impl FnMut(&Packet, &[usize]) -> bool,
+) -> Result<Optimized, OptimizeError> {
+    let original_bits = packet.bit_length(Encoding::Standard)?;
+    optimize_measured(packet, original_bits, fold)
+}
+
+// optimizes a hex transmission, measuring the original by the bits it actually used
+pub fn optimize_transmission(
+    input: &str,
+    fold: impl FnMut(&Packet, &[usize]) -> bool,
+) -> Result<Optimized, OptimizeError> {
+    let mut reader = HexReader::new(input)?;
+    let packet = parse_packet(&mut reader)?;
+    optimize_measured(&packet, reader.position, fold)
+}
+
+fn optimize_measured(
+    packet: &Packet,
+    original_bits: usize,
+    mut fold: impl FnMut(&Packet, &[usize]) -> bool,
+) -> Result<Optimized, OptimizeError> {
+    let (optimized, value) =
+        packet.try_fold(|packet, path, subpackets: Vec<(Packet, BigUint)>| {
+            let (operator, version) = match &packet.payload {
+                Payload::Literal(value) => {
+                    return Ok((literal(packet.version, value.clone()), value.clone()))
+                }
+                Payload::Operator(operator, _) => (*operator, packet.version),
+            };
+
+            let values: Vec<BigUint> = subpackets.iter().map(|(_, value)| value.clone()).collect();
+            let value = operator.evaluate_at(&values, path)?;
+            if fold(packet, path) {
+                return Ok((literal(version, value.clone()), value));
+            }
+
+            // merging never takes an operator past the sub-packets it can count, so the
+            // result can always be written compactly
+            let mut flattened = Vec::with_capacity(subpackets.len());
+            let mut remaining = subpackets.len();
+            for (mut subpacket, _) in subpackets {
+                remaining -= 1;
+                match &mut subpacket.payload {
+                    Payload::Operator(inner, packets)
+                        if *inner == operator
+                            && is_associative(operator)
+                            && flattened.len() + packets.len() + remaining
+                                <= MAX_SUBPACKET_COUNT =>
+                    {
+                        flattened.append(packets)
+                    }
+                    _ => flattened.push(subpacket),
+                }
+            }
+
+            Ok::<_, EvalError>((
+                Packet {
+                    version,
+                    payload: Payload::Operator(operator, flattened),
+                },
+                value,
+            ))
+        })?;
+
+    assert_eq!(
+        Ok(&value),
+        optimized.try_apply::<BigUint>().as_ref(),
+        "optimizing changed the value of the transmission"
+    );
+
+    Ok(Optimized {
+        original_bits,
+        optimized_bits: optimized.bit_length(Encoding::Compact)?,
+        packet: optimized,
+        value,
+    })
+}
+
+fn is_associative(operator: Operator) -> bool {
+    matches!(operator, Operator::Sum | Operator::Product)
+}
+
+fn literal(version: u64, value: BigUint) -> Packet {
+    Packet {
+        version,
+        payload: Payload::Literal(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::EvalErrorKind;
+    use crate::expression::to_packet;
+    use crate::fixtures::{deep_transmission, EXAMPLES, INPUT};
+    use crate::parse;
+
+    #[test]
+    fn test_flatten() {
+        let packet = to_packet("(1 + 2) + (3 + 4 * (5 * 6)) + min(7, 8 + 9)", || 0).unwrap();
+        let optimized = optimize(&packet, |_, _| false).unwrap();
+        assert_eq!(
+            "(+ 1 2 3 (* 4 5 6) (min 7 (+ 8 9)))",
+            optimized.packet.sexpr().to_string()
+        );
+        assert_eq!(BigUint::from(133u64), optimized.value);
+        assert!(optimized.optimized_bits < optimized.original_bits);
+        // the inner sum's version 2 goes with it
+        let mut next = 0;
+        let packet = to_packet("(1 + 2) + 3", || {
+            next += 1;
+            next
+        })
+        .unwrap();
+        let optimized = optimize(&packet, |_, _| false).unwrap();
+        assert_eq!(15, packet.version_sum());
+        assert_eq!(13, optimized.packet.version_sum());
+    }
+
+    #[test]
+    fn test_fold_everything() {
+        let packet = parse("9C0141080250320F1802104A08").unwrap();
+        let optimized = optimize(&packet, |_, _| true).unwrap();
+        assert_eq!("v4 literal 1", optimized.packet.to_string());
+        assert_eq!(11, optimized.optimized_bits);
+        assert_eq!(
+            packet.bit_length(Encoding::Standard).unwrap(),
+            optimized.original_bits
+        );
+    }
+
+    #[test]
+    fn test_fold_where_asked() {
+        // only the comparisons are folded, the sum around them stays
+        let packet = to_packet("(1 < 2) + (3 == 3) + 4", || 0).unwrap();
+        let optimized = optimize(
+            &packet,
+            |packet, _| matches!(&packet.payload, Payload::Operator(operator, _) if operator.is_comparison()),
+        )
+        .unwrap();
+        assert_eq!("(+ 1 1 4)", optimized.packet.sexpr().to_string());
+
+        // or only below the outermost packet
+        let optimized = optimize(&packet, |_, path| !path.is_empty()).unwrap();
+        assert_eq!("(+ 1 1 4)", optimized.packet.sexpr().to_string());
+    }
+
+    #[test]
+    fn test_transmission() {
+        // the sub-packets of this sum are counted, so it's already as small as it can be
+        let optimized = optimize_transmission("C200B40A82", |_, _| false).unwrap();
+        assert_eq!(40, optimized.original_bits);
+        assert_eq!(40, optimized.optimized_bits);
+
+        let optimized = optimize_transmission("C200B40A82", |_, _| true).unwrap();
+        assert_eq!(11, optimized.optimized_bits);
+        assert!(optimize_transmission("C200B4", |_, _| true).is_err());
+    }
+
+    #[test]
+    fn test_value_unchanged() {
+        for example in EXAMPLES.iter().chain([&INPUT]) {
+            let packet = parse(example).unwrap();
+            let optimized = optimize(&packet, |_, _| false).unwrap();
+            let reparsed = parse(&optimized.encode().unwrap()).unwrap();
+
+            assert_eq!(packet.apply(), reparsed.apply());
+            assert_eq!(
+                optimized.optimized_bits,
+                reparsed.to_bits(Encoding::Compact).unwrap().length
+            );
+            assert!(optimized.optimized_bits <= optimized.original_bits);
+        }
+    }
+
+    #[test]
+    fn test_merging_stays_countable() {
+        // merging both inner sums would leave 2100 sub-packets, more than can be counted
+        let source = format!(
+            "sum({}) + sum({})",
+            vec!["1"; 2000].join(", "),
+            vec!["1"; 100].join(", ")
+        );
+        let packet = to_packet(&source, || 0).unwrap();
+        let optimized = optimize(&packet, |_, _| false).unwrap();
+        match &optimized.packet.payload {
+            Payload::Operator(_, packets) => assert_eq!(2001, packets.len()),
+            Payload::Literal(_) => panic!("unexpected literal"),
+        }
+        assert_eq!(BigUint::from(2100u64), optimized.value);
+        assert!(optimized.encode().is_ok());
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let optimized = optimize_transmission(&deep_transmission(100_000), |_, _| false).unwrap();
+        assert_eq!(100_000 * 18 + 11, optimized.original_bits);
+        assert_eq!(optimized.original_bits, optimized.optimized_bits);
+        assert_eq!(BigUint::from(7u64), optimized.value);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut packet = to_packet("1 + (2 < 3)", || 0).unwrap();
+        if let Payload::Operator(_, packets) = &mut packet.payload {
+            if let Payload::Operator(_, packets) = &mut packets[1].payload {
+                packets.pop();
+            }
+        }
+        assert_eq!(
+            Some(EvalErrorKind::WrongArity(Operator::LessThan, 1)),
+            match optimize(&packet, |_, _| false) {
+                Err(OptimizeError::Eval(error)) => Some(error.kind),
+                _ => None,
+            }
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use crate::bignum::BigUint;
+use crate::eval::EvalError;
+use crate::operator::Operator;
+use crate::visitor::PacketVisitor;
+use crate::{Packet, Payload};
+use std::fmt;
+
+impl Operator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Sum => "+",
+            Operator::Product => "*",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => ">",
+            Operator::LessThan => "<",
+            Operator::EqualTo => "==",
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "minimum",
+            Operator::Maximum => "maximum",
+            Operator::GreaterThan => "greater than",
+            Operator::LessThan => "less than",
+            Operator::EqualTo => "equal to",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// one packet per line, sub-packets indented two spaces below their operator
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = TreeWriter { f, result: Ok(()) };
+        self.walk(&mut writer);
+        writer.result
+    }
+}
+
+// the printers write as packets are walked rather than recursing so any depth can be
+// printed, walks can't be stopped so they hold on to the first error and skip the rest
+struct TreeWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+}
+
+impl PacketVisitor for TreeWriter<'_, '_> {
+    fn enter(&mut self, packet: &Packet, path: &[usize]) {
+        if self.result.is_ok() {
+            self.result = self.write_line(packet, path.len());
+        }
+    }
+}
+
+impl TreeWriter<'_, '_> {
+    fn write_line(&mut self, packet: &Packet, depth: usize) -> fmt::Result {
+        if depth > 0 {
+            writeln!(self.f)?;
+        }
+
+        // a format width can't go past u16::MAX, so the indent goes out a chunk at a time
+        let mut indent = depth * 2;
+        while indent > 0 {
+            let chunk = indent.min(INDENT.len());
+            self.f.write_str(&INDENT[..chunk])?;
+            indent -= chunk;
+        }
+
+        write!(self.f, "v{} ", packet.version)?;
+        match &packet.payload {
+            Payload::Literal(value) => write!(self.f, "literal {}", value),
+            Payload::Operator(operator, _) => write!(self.f, "{}", operator),
+        }
+    }
+}
+
+const INDENT: &str = "                                                                ";
+
+impl Packet {
+    pub fn sexpr(&self) -> SExpr<'_> {
+        SExpr(self)
+    }
+
+    // a packet that can't be evaluated can't label its nodes with their values
+    pub fn dot(&self) -> Result<Dot<'_>, EvalError> {
+        self.try_apply::<BigUint>()?;
+        Ok(Dot(self))
+    }
+}
+
+// renders what a packet computes, like (+ 1 (* 2 3)), versions are left out
+pub struct SExpr<'a>(&'a Packet);
+
+impl fmt::Display for SExpr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = SExprWriter { f, result: Ok(()) };
+        self.0.walk(&mut writer);
+        writer.result
+    }
+}
+
+struct SExprWriter<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+}
+
+impl PacketVisitor for SExprWriter<'_, '_> {
+    fn enter(&mut self, packet: &Packet, path: &[usize]) {
+        if self.result.is_err() {
+            return;
+        }
+        let separator = if path.is_empty() { "" } else { " " };
+        self.result = match &packet.payload {
+            Payload::Literal(value) => write!(self.f, "{}{}", separator, value),
+            Payload::Operator(operator, _) => {
+                write!(self.f, "{}({}", separator, operator.symbol())
+            }
+        };
+    }
+
+    fn leave(&mut self, packet: &Packet, _: &[usize]) {
+        if self.result.is_ok() && matches!(packet.payload, Payload::Operator(..)) {
+            self.result = self.f.write_str(")");
+        }
+    }
+}
+
+// a Graphviz graph with a node per packet, render with `dot -Tsvg`
+pub struct Dot<'a>(&'a Packet);
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph transmission {{")?;
+        writeln!(f, "  node [shape=box];")?;
+
+        // nodes are numbered innermost first so each packet's value is worked out once,
+        // from the values already worked out for its sub-packets
+        let mut next = 0;
+        self.0
+            .try_fold(|packet, path, subpackets: Vec<(usize, BigUint)>| {
+                let id = next;
+                next += 1;
+
+                let value = match &packet.payload {
+                    Payload::Literal(value) => {
+                        writeln!(
+                            f,
+                            "  p{} [label=\"v{} literal\\n= {}\"];",
+                            id, packet.version, value
+                        )?;
+                        value.clone()
+                    }
+                    Payload::Operator(operator, _) => {
+                        let values: Vec<BigUint> =
+                            subpackets.iter().map(|(_, value)| value.clone()).collect();
+                        // dot already evaluated the packet, so this can't fail
+                        let value = operator
+                            .evaluate_at(&values, path)
+                            .map_err(|_| fmt::Error)?;
+                        writeln!(
+                            f,
+                            "  p{} [label=\"v{} {}\\n= {}\"];",
+                            id, packet.version, operator, value
+                        )?;
+                        for (subpacket, _) in &subpackets {
+                            writeln!(f, "  p{} -> p{};", id, subpacket)?;
+                        }
+                        value
+                    }
+                };
+                Ok((id, value))
+            })?;
+
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::EvalErrorKind;
+    use crate::fixtures::deep_transmission;
+    use crate::operator::Operator;
+    use crate::{parse, Payload};
+    use std::fmt::{self, Write};
+
+    // tallies what would have been written without keeping any of it
+    #[derive(Default)]
+    struct Counter {
+        length: usize,
+        closing: usize,
+    }
+
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.length += s.len();
+            self.closing += s.matches(')').count();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!("v6 literal 2021", parse("D2FE28").unwrap().to_string());
+        assert_eq!(
+            "v7 maximum\n  v2 literal 1\n  v4 literal 2\n  v1 literal 3",
+            parse("EE00D40C823060").unwrap().to_string()
+        );
+        assert_eq!(
+            "v4 minimum\n  v1 minimum\n    v5 minimum\n      v6 literal 15",
+            parse("8A004A801A8002F478").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_sexpr() {
+        assert_eq!("(+ 1 2)", parse("C200B40A82").unwrap().sexpr().to_string());
+        assert_eq!(
+            "(* 6 9)",
+            parse("04005AC33890").unwrap().sexpr().to_string()
+        );
+        assert_eq!(
+            "(< 5 15)",
+            parse("D8005AC2A8F0").unwrap().sexpr().to_string()
+        );
+        assert_eq!(
+            "(== (+ 1 3) (* 2 2))",
+            parse("9C0141080250320F1802104A08")
+                .unwrap()
+                .sexpr()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(
+            "digraph transmission {\n  node [shape=box];\n  p0 [label=\"v6 literal\\n= 2021\"];\n}\n",
+            parse("D2FE28").unwrap().dot().unwrap().to_string()
+        );
+
+        let dot = parse("9C0141080250320F1802104A08")
+            .unwrap()
+            .dot()
+            .unwrap()
+            .to_string();
+        let lines: Vec<_> = dot.lines().collect();
+        assert_eq!(
+            vec![
+                "digraph transmission {",
+                "  node [shape=box];",
+                "  p0 [label=\"v2 literal\\n= 1\"];",
+                "  p1 [label=\"v4 literal\\n= 3\"];",
+                "  p2 [label=\"v2 sum\\n= 4\"];",
+                "  p2 -> p0;",
+                "  p2 -> p1;",
+                "  p3 [label=\"v0 literal\\n= 2\"];",
+                "  p4 [label=\"v2 literal\\n= 2\"];",
+                "  p5 [label=\"v6 product\\n= 4\"];",
+                "  p5 -> p3;",
+                "  p5 -> p4;",
+                "  p6 [label=\"v4 equal to\\n= 1\"];",
+                "  p6 -> p2;",
+                "  p6 -> p5;",
+                "}",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_dot_errors() {
+        // a less than operator left with a single literal sub-packet
+        let mut packet = parse("9C0141080250320F1802104A08").unwrap();
+        packet.payload = Payload::Operator(Operator::LessThan, vec![parse("D2FE28").unwrap()]);
+        assert_eq!(
+            Some(EvalErrorKind::WrongArity(Operator::LessThan, 1)),
+            packet.dot().err().map(|error| error.kind)
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // deeper than a format width can indent
+        const DEPTH: usize = 33_000;
+        let packet = parse(&deep_transmission(DEPTH)).unwrap();
+
+        // every maximum is a line of its own, indented by its depth, above the literal
+        let mut counter = Counter::default();
+        write!(counter, "{}", packet).unwrap();
+        let indents = DEPTH * (DEPTH + 1);
+        assert_eq!(
+            indents + DEPTH * "v1 maximum\n".len() + "v0 literal 7".len(),
+            counter.length
+        );
+
+        let mut counter = Counter::default();
+        write!(counter, "{}", packet.sexpr()).unwrap();
+        assert_eq!(DEPTH * "(max )".len() + 1, counter.length);
+        assert_eq!(DEPTH, counter.closing);
+    }
+}
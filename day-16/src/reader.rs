@@ -0,0 +1,323 @@
+use crate::error::{DecodeError, DecodeErrorKind};
+
+// the forms a transmission can arrive in, text forms may be split up with whitespace or
+// underscores and hex may be lowercase or start with 0x
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Input<'a> {
+    Hex(&'a str),
+    Bits(&'a str),
+    Bytes(&'a [u8]),
+}
+
+// reads arbitrary width unsigned values out of a hex transmission, the hex is
+// decoded once into bytes and every read after that is just shifts and masks
+pub struct HexReader {
+    bytes: Vec<u8>,
+    length: usize,
+    pub position: usize,
+}
+
+impl HexReader {
+    pub fn new(hex_string: &str) -> Result<HexReader, DecodeError> {
+        let trimmed = hex_string.trim_start();
+        let digits = digits(
+            trimmed
+                .strip_prefix("0x")
+                .or_else(|| trimmed.strip_prefix("0X"))
+                .unwrap_or(trimmed),
+        );
+        Ok(HexReader {
+            bytes: hex_to_bytes(&digits)?,
+            length: digits.len() * 4,
+            position: 0,
+        })
+    }
+
+    // a string of 0s and 1s, one character per bit
+    pub fn from_bits(bit_string: &str) -> Result<HexReader, DecodeError> {
+        let digits = digits(bit_string);
+        Ok(HexReader {
+            bytes: bits_to_bytes(&digits)?,
+            length: digits.len(),
+            position: 0,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> HexReader {
+        HexReader {
+            bytes: bytes.to_vec(),
+            length: bytes.len() * 8,
+            position: 0,
+        }
+    }
+
+    pub fn from_input(input: Input) -> Result<HexReader, DecodeError> {
+        match input {
+            Input::Hex(hex_string) => HexReader::new(hex_string),
+            Input::Bits(bit_string) => HexReader::from_bits(bit_string),
+            Input::Bytes(bytes) => Ok(HexReader::from_bytes(bytes)),
+        }
+    }
+
+    // bits left between the current position and the end of the transmission
+    pub fn remaining(&self) -> usize {
+        self.length - self.position
+    }
+
+    pub fn read_decimal(&mut self, length: usize) -> Result<u64, DecodeError> {
+        let value = self.read_at(self.position, length)?;
+        self.position += length;
+        Ok(value)
+    }
+
+    // reads without moving the position, for anything holding on to offsets of its own
+    pub fn read_at(&self, offset: usize, length: usize) -> Result<u64, DecodeError> {
+        if length > 64 {
+            panic!(
+                "unable to read more than 64 bits at once, asked for: {}",
+                length
+            );
+        }
+
+        if offset + length > self.length {
+            return Err(DecodeError::new(DecodeErrorKind::Truncated, offset));
+        }
+
+        let mut value = 0;
+        let mut position = offset;
+        while position < offset + length {
+            let byte = self.bytes[position / 8];
+            let available = 8 - position % 8;
+            let take = (offset + length - position).min(available);
+            let bits = (byte as u64 >> (available - take)) & ((1 << take) - 1);
+
+            value = (value << take) | bits;
+            position += take;
+        }
+        Ok(value)
+    }
+}
+
+// drops the characters that are only there to make a transmission easier to read
+fn digits(value: &str) -> String {
+    value
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != '_')
+        .collect()
+}
+
+fn hex_to_bytes(value: &str) -> Result<Vec<u8>, DecodeError> {
+    let nibbles = value
+        .chars()
+        .enumerate()
+        .map(|(index, character)| match character.to_digit(16) {
+            Some(digit) => Ok(digit as u8),
+            None => Err(DecodeError::new(
+                DecodeErrorKind::InvalidHexDigit(character),
+                index * 4,
+            )),
+        })
+        .collect::<Result<Vec<u8>, DecodeError>>()?;
+
+    // an odd number of hex characters leaves the low nibble of the last byte empty
+    Ok(nibbles
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).unwrap_or(&0))
+        .collect())
+}
+
+fn bits_to_bytes(value: &str) -> Result<Vec<u8>, DecodeError> {
+    let bits = value
+        .chars()
+        .enumerate()
+        .map(|(index, character)| match character {
+            '0' | '1' => Ok((character == '1') as u8),
+            _ => Err(DecodeError::new(
+                DecodeErrorKind::InvalidBit(character),
+                index,
+            )),
+        })
+        .collect::<Result<Vec<u8>, DecodeError>>()?;
+
+    // the last byte is filled out with zeros like it is for hex
+    Ok(bits
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (index, bit)| byte | bit << (7 - index))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_hex_to_bytes() {
+        assert_eq!(
+            vec![0xEE, 0x00, 0xD4, 0x0C, 0x82, 0x30, 0x60],
+            hex_to_bytes("EE00D40C823060").unwrap()
+        );
+        assert_eq!(vec![0xA0, 0x10], hex_to_bytes("A01").unwrap());
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::InvalidHexDigit('G'), 8)),
+            hex_to_bytes("A0G1")
+        );
+        assert_eq!(vec![0xAB], hex_to_bytes("ab").unwrap());
+    }
+
+    #[test]
+    fn test_forgiving_hex() {
+        for hex in [
+            "ee00d40c823060",
+            "0xEE00D40C823060",
+            "  0Xee00_d40c_8230_60\n",
+            "EE 00 D4 0C 82 30 60",
+        ] {
+            let mut reader = HexReader::new(hex).unwrap();
+            assert_eq!(56, reader.remaining(), "{}", hex);
+            assert_eq!(Ok(0xEE00D40C823060), reader.read_decimal(56), "{}", hex);
+        }
+
+        // offsets count the bits before the bad digit, not the characters
+        assert_eq!(
+            Some(DecodeError::new(DecodeErrorKind::InvalidHexDigit('G'), 12)),
+            HexReader::new("0x_A0 1G").err()
+        );
+        assert_eq!(
+            Some(DecodeError::new(DecodeErrorKind::InvalidHexDigit('x'), 8)),
+            HexReader::new("A0x1").err()
+        );
+    }
+
+    #[test]
+    fn test_other_inputs() {
+        let mut reader = HexReader::from_bits("1110_1110 0000 0").unwrap();
+        assert_eq!(13, reader.remaining());
+        assert_eq!(Ok(0b1110111000000), reader.read_decimal(13));
+        assert_eq!(
+            Some(DecodeError::new(DecodeErrorKind::InvalidBit('2'), 3)),
+            HexReader::from_bits("10 12").err()
+        );
+
+        let mut reader = HexReader::from_input(Input::Bytes(&[0xEE, 0x00, 0xD4])).unwrap();
+        assert_eq!(24, reader.remaining());
+        assert_eq!(Ok(7), reader.read_decimal(3));
+
+        let mut reader = HexReader::from_input(Input::Bits("111")).unwrap();
+        assert_eq!(Ok(7), reader.read_decimal(3));
+    }
+
+    #[test]
+    fn test_hex_reader() {
+        let mut reader = HexReader::new("EE00D40C823060").unwrap();
+        assert_eq!(Ok(7), reader.read_decimal(3));
+        assert_eq!(Ok(3), reader.read_decimal(3));
+        assert_eq!(Ok(1), reader.read_decimal(1));
+        assert_eq!(Ok(3), reader.read_decimal(11));
+        assert_eq!(18, reader.position);
+        assert_eq!(38, reader.remaining());
+    }
+
+    #[test]
+    fn test_wide_reads() {
+        let mut reader = HexReader::new("0123456789ABCDEF0F").unwrap();
+        assert_eq!(Ok(0x0), reader.read_decimal(4));
+        assert_eq!(Ok(0x123456789ABCDEF0), reader.read_decimal(64));
+        assert_eq!(Ok(0xF), reader.read_decimal(4));
+        assert_eq!(Ok(0), reader.read_decimal(0));
+    }
+
+    #[test]
+    fn test_read_at() {
+        let reader = HexReader::new("EE00D40C823060").unwrap();
+        assert_eq!(Ok(3), reader.read_at(7, 11));
+        assert_eq!(Ok(7), reader.read_at(0, 3));
+        assert_eq!(0, reader.position);
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::Truncated, 50)),
+            reader.read_at(50, 7)
+        );
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let mut reader = HexReader::new("A").unwrap();
+        assert_eq!(Ok(1), reader.read_decimal(1));
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::Truncated, 1)),
+            reader.read_decimal(5)
+        );
+    }
+
+    // the reader this one replaced, kept around so the benchmark has a baseline
+    struct StringReader {
+        packet: String,
+        position: usize,
+    }
+
+    impl StringReader {
+        fn read_decimal(&mut self, length: usize) -> u64 {
+            let slice = &self.packet[self.position..self.position + length];
+            self.position += length;
+            u64::from_str_radix(slice, 2).unwrap()
+        }
+    }
+
+    // run with `cargo test --release -p day-16 -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_real_input() {
+        const ITERATIONS: usize = 10_000;
+        const WIDTHS: [usize; 5] = [3, 3, 1, 15, 11];
+        let input = include_str!("input.txt").trim();
+
+        let read_all = |read: &mut dyn FnMut(usize) -> u64| {
+            let mut total = 0;
+            let mut remaining = input.len() * 4;
+            for width in WIDTHS.iter().cycle() {
+                if *width > remaining {
+                    break;
+                }
+                total += read(*width);
+                remaining -= width;
+            }
+            total
+        };
+
+        let started = Instant::now();
+        let mut expected = 0;
+        for _ in 0..ITERATIONS {
+            let packet = input
+                .chars()
+                .map(|character| format!("{:04b}", character.to_digit(16).unwrap()))
+                .collect();
+            let mut reader = StringReader {
+                packet,
+                position: 0,
+            };
+            expected = read_all(&mut |width| reader.read_decimal(width));
+        }
+        let string_elapsed = started.elapsed();
+
+        let started = Instant::now();
+        let mut actual = 0;
+        for _ in 0..ITERATIONS {
+            let mut reader = HexReader::new(input).unwrap();
+            actual = read_all(&mut |width| reader.read_decimal(width).unwrap());
+        }
+        let bytes_elapsed = started.elapsed();
+
+        assert_eq!(expected, actual);
+        println!(
+            "string reader: {:?}, byte reader: {:?} ({:.1}x)",
+            string_elapsed,
+            bytes_elapsed,
+            string_elapsed.as_secs_f64() / bytes_elapsed.as_secs_f64()
+        );
+    }
+}
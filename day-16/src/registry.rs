@@ -0,0 +1,320 @@
+use crate::error::DecodeError;
+use crate::operator::Operator;
+use crate::reader::HexReader;
+use crate::view::PacketView;
+use crate::LITERAL_TYPE_ID;
+use std::collections::HashMap;
+use std::fmt;
+
+// the type id is a 3 bit field, so there's no room for new ids, only new meanings for old ones,
+// giving xor and average ids 8 and 9 would take an extension to the header format itself
+pub const TYPE_ID_COUNT: u64 = 8;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(arity) => count == arity,
+            Arity::AtLeast(arity) => count >= arity,
+        }
+    }
+}
+
+// None when there's no answer, say from an overflow
+pub type Handler = Box<dyn Fn(&[u64]) -> Option<u64>>;
+
+struct Definition {
+    name: String,
+    arity: Arity,
+    handler: Handler,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RegisterError {
+    // type id 4 always means a literal, the decoder reads its groups differently
+    Literal,
+    // ids past 7 can't be written in a packet header
+    OutOfRange(u64),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::Literal => write!(f, "type id {} is for literals", LITERAL_TYPE_ID),
+            RegisterError::OutOfRange(type_id) => write!(
+                f,
+                "type id {} doesn't fit in a {} bit header field",
+                type_id,
+                TYPE_ID_COUNT.trailing_zeros()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ExtensionErrorKind {
+    Decode(DecodeError),
+    WrongArity(String, usize),
+    Failed(String),
+}
+
+// path holds the index of each sub-packet taken from the outermost packet down
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExtensionError {
+    pub kind: ExtensionErrorKind,
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for ExtensionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExtensionErrorKind::Decode(error) => write!(f, "{}", error)?,
+            ExtensionErrorKind::WrongArity(name, count) => {
+                write!(f, "{} can't take {} sub-packets", name, count)?
+            }
+            ExtensionErrorKind::Failed(name) => write!(f, "{} has no result", name)?,
+        }
+        write!(f, " at packet {:?}", self.path)
+    }
+}
+
+impl std::error::Error for ExtensionError {}
+
+// what each operator type id means when evaluating, starting out as the built-in operators.
+// evaluation goes through a PacketView rather than a Packet so the decoder's arity checks
+// for the built-ins don't get in the way of operators that have been redefined
+pub struct Registry {
+    definitions: HashMap<u64, Definition>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        let mut registry = Registry {
+            definitions: HashMap::new(),
+        };
+        for operator in Operator::ALL {
+            let arity = match operator.is_comparison() {
+                true => Arity::Exactly(2),
+                false => Arity::AtLeast(1),
+            };
+            registry
+                .register(
+                    operator.type_id(),
+                    &operator.to_string(),
+                    arity,
+                    move |values| operator.try_evaluate(values),
+                )
+                .unwrap();
+        }
+        registry
+    }
+
+    // replaces whatever type_id meant before
+    pub fn register(
+        &mut self,
+        type_id: u64,
+        name: &str,
+        arity: Arity,
+        handler: impl Fn(&[u64]) -> Option<u64> + 'static,
+    ) -> Result<(), RegisterError> {
+        if type_id == LITERAL_TYPE_ID {
+            return Err(RegisterError::Literal);
+        }
+        if type_id >= TYPE_ID_COUNT {
+            return Err(RegisterError::OutOfRange(type_id));
+        }
+
+        self.definitions.insert(
+            type_id,
+            Definition {
+                name: name.to_string(),
+                arity,
+                handler: Box::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn name(&self, type_id: u64) -> Option<&str> {
+        self.definitions
+            .get(&type_id)
+            .map(|definition| definition.name.as_str())
+    }
+
+    pub fn evaluate(&self, input: &str) -> Result<u64, ExtensionError> {
+        let reader = HexReader::new(input).map_err(|error| ExtensionError {
+            kind: ExtensionErrorKind::Decode(error),
+            path: Vec::new(),
+        })?;
+        self.evaluate_view(&PacketView::new(&reader))
+    }
+
+    pub fn evaluate_view(&self, view: &PacketView) -> Result<u64, ExtensionError> {
+        let error = |kind, path: &[usize]| ExtensionError {
+            kind,
+            path: path.to_vec(),
+        };
+        let decode =
+            |decode_error, path: &[usize]| error(ExtensionErrorKind::Decode(decode_error), path);
+
+        let (value, _) = view.traverse(decode, |view, path, values: Vec<u64>| {
+            let type_id = view.type_id().map_err(|e| decode(e, path))?;
+            if type_id == LITERAL_TYPE_ID {
+                let literal = view.literal().map_err(|e| decode(e, path))?.unwrap();
+                let name = "literal".to_string();
+                return literal
+                    .to_u64()
+                    .ok_or_else(|| error(ExtensionErrorKind::Failed(name), path));
+            }
+
+            // every id other than the literal one starts out defined and can only be replaced
+            let definition = &self.definitions[&type_id];
+            if !definition.arity.accepts(values.len()) {
+                return Err(error(
+                    ExtensionErrorKind::WrongArity(definition.name.clone(), values.len()),
+                    path,
+                ));
+            }
+            (definition.handler)(&values)
+                .ok_or_else(|| error(ExtensionErrorKind::Failed(definition.name.clone()), path))
+        })?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bignum::BigUint;
+    use crate::expression::to_packet;
+    use crate::fixtures::{deep_transmission, EXAMPLES, INPUT};
+    use crate::{parse, Packet, Payload};
+
+    fn xor(values: &[u64]) -> Option<u64> {
+        Some(values.iter().fold(0, |total, value| total ^ value))
+    }
+
+    fn average(values: &[u64]) -> Option<u64> {
+        let total = values
+            .iter()
+            .try_fold(0u64, |total, value| total.checked_add(*value))?;
+        Some(total / values.len() as u64)
+    }
+
+    #[test]
+    fn test_builtins() {
+        let registry = Registry::new();
+        for example in EXAMPLES.iter().chain([&INPUT]) {
+            assert_eq!(
+                Ok(parse(example).unwrap().apply()),
+                registry.evaluate(example)
+            );
+        }
+        assert_eq!(Some("maximum"), registry.name(3));
+        assert_eq!(None, registry.name(4));
+    }
+
+    #[test]
+    fn test_redefine() {
+        let mut registry = Registry::new();
+        registry.register(7, "xor", Arity::AtLeast(1), xor).unwrap();
+        registry
+            .register(5, "average", Arity::AtLeast(1), average)
+            .unwrap();
+
+        // equal to and greater than each given a third sub-packet, which parse would turn away
+        let mut packet = to_packet("(1 + 2 == 6) + (3 > 4) + 5", || 0).unwrap();
+        if let Payload::Operator(_, packets) = &mut packet.payload {
+            for (index, value) in [(0, 7u64), (1, 8)] {
+                if let Payload::Operator(_, comparison) = &mut packets[index].payload {
+                    comparison.push(Packet {
+                        version: 0,
+                        payload: Payload::Literal(BigUint::from(value)),
+                    });
+                }
+            }
+        }
+        let hex = packet.encode().unwrap();
+        assert!(parse(&hex).is_err());
+        assert_eq!(
+            Ok((3 ^ 6 ^ 7) + (3 + 4 + 8) / 3 + 5),
+            registry.evaluate(&hex)
+        );
+
+        let mut writer = crate::encoder::BitWriter::new();
+        // version 0, type id 5, three counted literals 3, 4 and 8
+        writer.write(0, 3);
+        writer.write(5, 3);
+        writer.write(1, 1);
+        writer.write(3, 11);
+        for value in [3, 4, 8] {
+            writer.write(0, 3);
+            writer.write(LITERAL_TYPE_ID, 3);
+            writer.write(value, 5);
+        }
+        let hex = writer.to_hex();
+        assert!(parse(&hex).is_err());
+        assert_eq!(Ok(5), registry.evaluate(&hex));
+        assert_eq!(Some("average"), registry.name(5));
+    }
+
+    #[test]
+    fn test_register_errors() {
+        let mut registry = Registry::new();
+        assert_eq!(
+            Err(RegisterError::OutOfRange(8)),
+            registry.register(8, "xor", Arity::AtLeast(1), xor)
+        );
+        assert_eq!(
+            Err(RegisterError::Literal),
+            registry.register(4, "average", Arity::AtLeast(1), average)
+        );
+        assert_eq!(
+            "type id 9 doesn't fit in a 3 bit header field",
+            RegisterError::OutOfRange(9).to_string()
+        );
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let mut registry = Registry::new();
+        registry
+            .register(0, "pair", Arity::Exactly(2), |values| Some(values[0]))
+            .unwrap();
+        assert_eq!(
+            Err(ExtensionError {
+                kind: ExtensionErrorKind::WrongArity("pair".to_string(), 3),
+                path: vec![1],
+            }),
+            registry.evaluate(
+                &to_packet("1 * (2 + 3 + 4)", || 0)
+                    .unwrap()
+                    .encode()
+                    .unwrap()
+            )
+        );
+
+        let error = Registry::new().evaluate("C200B4").unwrap_err();
+        assert_eq!(vec![0], error.path);
+        assert!(matches!(error.kind, ExtensionErrorKind::Decode(_)));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let registry = Registry::new();
+        assert_eq!(Ok(7), registry.evaluate(&deep_transmission(100_000)));
+    }
+}
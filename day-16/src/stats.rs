@@ -0,0 +1,166 @@
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::operator::Operator;
+use crate::reader::HexReader;
+use crate::view::PacketView;
+use std::fmt;
+
+// a summary of the shape of a transmission, bit counts cover the whole hex input so
+// total_bits is always packet_bits plus padding_bits
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Stats {
+    pub packets: usize,
+    pub literals: usize,
+    pub operators: usize,
+    // indexed the same as Operator::ALL
+    pub operator_counts: [usize; 7],
+    pub version_counts: [usize; 8],
+    pub max_depth: usize,
+    pub total_bits: usize,
+    pub packet_bits: usize,
+    // the 4 bit value groups of every literal, everything else in a packet is overhead
+    pub payload_bits: usize,
+    pub padding_bits: usize,
+}
+
+// a single pass over the transmission without building the packet tree, so it copes with
+// any depth. errors are the same ones parse would give
+pub fn stats(input: &str) -> Result<Stats, DecodeError> {
+    let reader = HexReader::new(input)?;
+    let mut stats = Stats::default();
+
+    let (_, end) = PacketView::new(&reader).traverse(
+        |error, _| error,
+        |view, path, children: Vec<()>| {
+            stats.packets += 1;
+            stats.version_counts[view.version()? as usize] += 1;
+            stats.max_depth = stats.max_depth.max(path.len() + 1);
+            match view.operator()? {
+                None => {
+                    // a 6 bit header followed by 5 bit groups each carrying 4 bits of value
+                    stats.literals += 1;
+                    stats.payload_bits += (view.end()? - view.offset() - 6) / 5 * 4;
+                }
+                Some(operator) if !operator.accepts(children.len()) => {
+                    return Err(DecodeError::new(
+                        DecodeErrorKind::WrongArity(operator, children.len()),
+                        view.offset(),
+                    ))
+                }
+                Some(operator) => {
+                    stats.operators += 1;
+                    let index = Operator::ALL.iter().position(|o| *o == operator).unwrap();
+                    stats.operator_counts[index] += 1;
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    // nothing has been read yet, so everything remains
+    stats.total_bits = reader.remaining();
+    stats.packet_bits = end;
+    stats.padding_bits = stats.total_bits - end;
+    Ok(stats)
+}
+
+fn percentage(part: usize, whole: usize) -> f64 {
+    match whole {
+        0 => 0.0,
+        _ => part as f64 * 100.0 / whole as f64,
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "packets        {}", self.packets)?;
+        writeln!(f, "  literals     {}", self.literals)?;
+        writeln!(f, "  operators    {}", self.operators)?;
+        writeln!(f, "maximum depth  {}", self.max_depth)?;
+
+        writeln!(f, "operators")?;
+        for (operator, count) in Operator::ALL.iter().zip(self.operator_counts) {
+            writeln!(f, "  {:<13}{}", operator.to_string(), count)?;
+        }
+
+        writeln!(f, "versions")?;
+        for (version, count) in self.version_counts.iter().enumerate() {
+            writeln!(f, "  {:<13}{}", version, count)?;
+        }
+
+        writeln!(f, "total bits     {}", self.total_bits)?;
+        writeln!(
+            f,
+            "  packet bits  {} ({:.1}% payload)",
+            self.packet_bits,
+            percentage(self.payload_bits, self.packet_bits)
+        )?;
+        writeln!(f, "  payload bits {}", self.payload_bits)?;
+        writeln!(f, "  padding bits {}", self.padding_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::deep_transmission;
+
+    #[test]
+    fn test_literal() {
+        let stats = stats("D2FE28").unwrap();
+        assert_eq!(1, stats.packets);
+        assert_eq!(1, stats.literals);
+        assert_eq!(0, stats.operators);
+        assert_eq!(1, stats.max_depth);
+        assert_eq!(1, stats.version_counts[6]);
+        assert_eq!(24, stats.total_bits);
+        assert_eq!(21, stats.packet_bits);
+        assert_eq!(12, stats.payload_bits);
+        assert_eq!(3, stats.padding_bits);
+    }
+
+    #[test]
+    fn test_nested() {
+        // an operator containing an operator containing an operator containing five literals
+        let stats = stats("A0016C880162017C3686B18A3D4780").unwrap();
+        assert_eq!(8, stats.packets);
+        assert_eq!(5, stats.literals);
+        assert_eq!(3, stats.operators);
+        assert_eq!(4, stats.max_depth);
+        assert_eq!(3, stats.operator_counts[0]);
+        assert_eq!(8, stats.version_counts.iter().sum::<usize>());
+        assert_eq!(stats.total_bits, stats.packet_bits + stats.padding_bits);
+        assert_eq!(120, stats.total_bits);
+    }
+
+    #[test]
+    fn test_rendering() {
+        let rendered = stats("EE00D40C823060").unwrap().to_string();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!("packets        4", lines[0]);
+        assert_eq!("  maximum      1", lines[8]);
+        assert_eq!("  2            1", lines[15]);
+        assert_eq!("  packet bits  51 (23.5% payload)", lines[22]);
+        assert_eq!("  padding bits 5", lines[24]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(stats("D2FE").is_err());
+        assert_eq!(
+            Err(DecodeError::new(
+                DecodeErrorKind::WrongArity(Operator::LessThan, 1),
+                0
+            )),
+            stats("38002C408")
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let stats = stats(&deep_transmission(100_000)).unwrap();
+        assert_eq!(100_001, stats.packets);
+        assert_eq!(100_001, stats.max_depth);
+        assert_eq!(100_000, stats.operator_counts[3]);
+        assert_eq!(100_000 * 18 + 11, stats.packet_bits);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::eval::{EvalError, EvalErrorKind};
+use crate::operator::Operator;
+use crate::{Packet, Payload};
+use std::fmt;
+
+// a single operator being evaluated, steps only point back at their parent rather than
+// carrying a whole path, Trace::path puts it back together
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Step {
+    // how many packets enclose this one, and where it sits among its parent's sub-packets
+    pub depth: usize,
+    pub position: usize,
+    // the step for the enclosing operator, None for the outermost one and for any whose
+    // parent never got evaluated because of an error
+    pub parent: Option<usize>,
+    pub operator: Operator,
+    pub inputs: Vec<u64>,
+    pub result: u64,
+}
+
+// every operator evaluated in the order apply works them out, innermost first, stopping
+// at the first one that goes wrong
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub result: Result<u64, EvalError>,
+}
+
+impl Trace {
+    // the path to a step's packet from the outermost one
+    pub fn path(&self, step: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut step = &self.steps[step];
+        while step.depth > 0 {
+            path.push(step.position);
+            match step.parent {
+                Some(parent) => step = &self.steps[parent],
+                None => {
+                    // an operator that's still waiting on its parent can only be one the
+                    // error stopped, so the rest of the way is on the error's path
+                    if let Err(error) = &self.result {
+                        path.extend(error.path[..step.depth - 1].iter().rev());
+                    }
+                    break;
+                }
+            }
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl Packet {
+    pub fn apply_traced(&self) -> Trace {
+        let mut steps: Vec<Step> = Vec::new();
+        // each value comes with the step that worked it out, if an operator did
+        let result =
+            self.try_fold(|packet, path, inputs: Vec<(u64, Option<usize>)>| {
+                match &packet.payload {
+                    Payload::Literal(value) => value
+                        .to_u64()
+                        .map(|value| (value, None))
+                        .ok_or_else(|| EvalError {
+                            kind: EvalErrorKind::LiteralTooLarge,
+                            path: path.to_vec(),
+                        }),
+                    Payload::Operator(operator, _) => {
+                        let values: Vec<u64> = inputs.iter().map(|(value, _)| *value).collect();
+                        let result = operator.evaluate_at(&values, path)?;
+                        for (_, step) in &inputs {
+                            if let Some(step) = *step {
+                                steps[step].parent = Some(steps.len());
+                            }
+                        }
+                        steps.push(Step {
+                            depth: path.len(),
+                            position: path.last().copied().unwrap_or(0),
+                            parent: None,
+                            operator: *operator,
+                            inputs: values,
+                            result,
+                        });
+                        Ok((result, Some(steps.len() - 1)))
+                    }
+                }
+            });
+
+        Trace {
+            steps,
+            result: result.map(|(value, _)| value),
+        }
+    }
+}
+
+// lines look like `sum 1, 3 = 4`
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inputs: Vec<String> = self.inputs.iter().map(|input| input.to_string()).collect();
+        write!(
+            f,
+            "{} {} = {}",
+            self.operator,
+            inputs.join(", "),
+            self.result
+        )
+    }
+}
+
+// lines look like `[0, 1]  sum 1, 3 = 4`
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{:<12} {}", format!("{:?}", self.path(index)), step)?;
+        }
+        match &self.result {
+            Ok(value) => writeln!(f, "result {}", value),
+            Err(error) => writeln!(f, "error: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::to_packet;
+    use crate::fixtures::{deep_transmission, INPUT};
+    use crate::parse;
+
+    #[test]
+    fn test_trace() {
+        let trace = parse("9C0141080250320F1802104A08").unwrap().apply_traced();
+        assert_eq!(Ok(1), trace.result);
+        assert_eq!(
+            vec![
+                Step {
+                    depth: 1,
+                    position: 0,
+                    parent: Some(2),
+                    operator: Operator::Sum,
+                    inputs: vec![1, 3],
+                    result: 4,
+                },
+                Step {
+                    depth: 1,
+                    position: 1,
+                    parent: Some(2),
+                    operator: Operator::Product,
+                    inputs: vec![2, 2],
+                    result: 4,
+                },
+                Step {
+                    depth: 0,
+                    position: 0,
+                    parent: None,
+                    operator: Operator::EqualTo,
+                    inputs: vec![4, 4],
+                    result: 1,
+                },
+            ],
+            trace.steps
+        );
+        assert_eq!(
+            vec![vec![0], vec![1], vec![]],
+            (0..3).map(|step| trace.path(step)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_matches_apply() {
+        let packet = parse(INPUT).unwrap();
+        let trace = packet.apply_traced();
+        assert_eq!(Ok(packet.apply()), trace.result);
+        assert_eq!(Vec::<usize>::new(), trace.path(trace.steps.len() - 1));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let trace = parse(&deep_transmission(DEPTH)).unwrap().apply_traced();
+        assert_eq!(Ok(7), trace.result);
+        assert_eq!(DEPTH, trace.steps.len());
+        assert_eq!(vec![0; DEPTH - 1], trace.path(0));
+    }
+
+    #[test]
+    fn test_stops_at_error() {
+        let packet = to_packet("max(2 * 3, 18446744073709551615 + 1, 7)", || 0).unwrap();
+        let trace = packet.apply_traced();
+        assert_eq!(1, trace.steps.len());
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::Overflow(Operator::Sum),
+                path: vec![1],
+            }),
+            trace.result
+        );
+
+        // the product's parent never finished, its path still comes out whole
+        let packet = to_packet("max(1, (2 * 3) + 18446744073709551615)", || 0).unwrap();
+        let trace = packet.apply_traced();
+        assert_eq!(None, trace.steps[0].parent);
+        assert_eq!(vec![1, 0], trace.path(0));
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        let mut packet = to_packet("1 < 2", || 0).unwrap();
+        if let Payload::Operator(_, packets) = &mut packet.payload {
+            packets.pop();
+        }
+        assert_eq!(
+            Err(EvalError {
+                kind: EvalErrorKind::WrongArity(Operator::LessThan, 1),
+                path: vec![],
+            }),
+            packet.apply_traced().result
+        );
+    }
+
+    #[test]
+    fn test_rendering() {
+        let packet = to_packet("min(1 + 3, 2 * 2 * 5) > 3", || 0).unwrap();
+        assert_eq!(
+            "[0, 0]       sum 1, 3 = 4\n\
+             [0, 1]       product 2, 2, 5 = 20\n\
+             [0]          minimum 4, 20 = 4\n\
+             []           greater than 4, 3 = 1\n\
+             result 1\n",
+            packet.apply_traced().to_string()
+        );
+
+        let packet = to_packet("2 * 3 + 18446744073709551615", || 0).unwrap();
+        assert_eq!(
+            "[0]          product 2, 3 = 6\n\
+             error: Sum overflowed at packet []\n",
+            packet.apply_traced().to_string()
+        );
+    }
+}
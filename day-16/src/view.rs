@@ -0,0 +1,442 @@
+use crate::bignum::BigUint;
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::eval::{EvalError, EvalErrorKind, Value};
+use crate::operator::Operator;
+use crate::reader::HexReader;
+use crate::{read_header, Body, Ending, Header, LITERAL_TYPE_ID};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ViewError {
+    Decode(DecodeError),
+    Eval(EvalError),
+}
+
+impl From<DecodeError> for ViewError {
+    fn from(error: DecodeError) -> Self {
+        ViewError::Decode(error)
+    }
+}
+
+impl From<EvalError> for ViewError {
+    fn from(error: EvalError) -> Self {
+        ViewError::Eval(error)
+    }
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::Decode(error) => write!(f, "{}", error),
+            ViewError::Eval(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ViewError {}
+
+// a packet read straight out of the decoded transmission, nothing is decoded until it's
+// asked for so header fields of huge transmissions are cheap to get at
+#[derive(Clone, Copy)]
+pub struct PacketView<'a> {
+    reader: &'a HexReader,
+    offset: usize,
+}
+
+impl<'a> PacketView<'a> {
+    // the outermost packet of the transmission
+    pub fn new(reader: &'a HexReader) -> PacketView<'a> {
+        PacketView { reader, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn version(&self) -> Result<u64, DecodeError> {
+        self.reader.read_at(self.offset, 3)
+    }
+
+    pub fn type_id(&self) -> Result<u64, DecodeError> {
+        self.reader.read_at(self.offset + 3, 3)
+    }
+
+    // None for literals
+    pub fn operator(&self) -> Result<Option<Operator>, DecodeError> {
+        let type_id = self.type_id()?;
+        if type_id == LITERAL_TYPE_ID {
+            return Ok(None);
+        }
+        match Operator::from_type_id(type_id) {
+            Some(operator) => Ok(Some(operator)),
+            None => Err(DecodeError::new(
+                DecodeErrorKind::UnknownTypeId(type_id),
+                self.offset + 3,
+            )),
+        }
+    }
+
+    // None for operators
+    pub fn literal(&self) -> Result<Option<BigUint>, DecodeError> {
+        if self.operator()?.is_some() {
+            return Ok(None);
+        }
+        match read_header(self.reader, self.offset)?.body {
+            Body::Literal(value) => Ok(Some(value)),
+            Body::Operator(..) => Ok(None),
+        }
+    }
+
+    // lazily steps through the sub-packets, empty for literals
+    pub fn children(&self) -> Children<'a> {
+        let (position, ending, error) = match read_header(self.reader, self.offset) {
+            Ok(Header {
+                body: Body::Operator(_, ending),
+                end,
+                ..
+            }) => (end, Some(ending), None),
+            Ok(header) => (header.end, None, None),
+            Err(error) => (self.offset, None, Some(error)),
+        };
+
+        Children {
+            reader: self.reader,
+            position,
+            ending,
+            count: 0,
+            error,
+        }
+    }
+
+    // straight from the header when the operator counts its sub-packets, otherwise
+    // they have to be stepped over
+    pub fn child_count(&self) -> Result<usize, DecodeError> {
+        match read_header(self.reader, self.offset)?.body {
+            Body::Literal(_) => Ok(0),
+            Body::Operator(_, Ending::Count(count)) => Ok(count as usize),
+            Body::Operator(_, Ending::Position(_)) => {
+                self.children().try_fold(0, |count, child| {
+                    child?;
+                    Ok(count + 1)
+                })
+            }
+        }
+    }
+
+    // the offset just past the last bit of this packet, only counted sub-packets need
+    // stepping over
+    pub fn end(&self) -> Result<usize, DecodeError> {
+        let header = read_header(self.reader, self.offset)?;
+        match header.body {
+            Body::Literal(_) => Ok(header.end),
+            Body::Operator(_, Ending::Position(ending)) => Ok(ending),
+            Body::Operator(_, Ending::Count(_)) => {
+                let (_, end) = self.traverse(|error, _| error, |_, _, _: Vec<()>| Ok(()))?;
+                Ok(end)
+            }
+        }
+    }
+
+    pub fn version_sum(&self) -> Result<u64, DecodeError> {
+        self.try_fold(|view, _, sums: Vec<u64>| Ok(view.version()? + sums.iter().sum::<u64>()))
+    }
+
+    pub fn apply(&self) -> Result<u64, ViewError> {
+        self.try_apply()
+    }
+
+    // evaluates like Packet::try_apply on the parsed packet, paths are reported the same
+    // way, though anything parse would turn away, wrong arity included, is a decode error
+    pub fn try_apply<V: Value>(&self) -> Result<V, ViewError> {
+        self.try_fold(|view, path, values: Vec<V>| {
+            let error = |kind| EvalError {
+                kind,
+                path: path.to_vec(),
+            };
+
+            let operator = match view.operator()? {
+                Some(operator) => operator,
+                None => {
+                    let literal = view.literal()?.unwrap();
+                    return V::from_literal(&literal)
+                        .ok_or_else(|| error(EvalErrorKind::LiteralTooLarge).into());
+                }
+            };
+
+            operator
+                .try_evaluate(&values)
+                .ok_or_else(|| error(EvalErrorKind::Overflow(operator)).into())
+        })
+    }
+
+    // combines every packet with the results of its sub-packets, innermost first, in a
+    // single pass over the transmission. operators with the wrong number of sub-packets
+    // are turned away just as parse would before combine sees them
+    pub fn try_fold<T, E: From<DecodeError>>(
+        &self,
+        mut combine: impl FnMut(PacketView<'a>, &[usize], Vec<T>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let (value, _) = self.traverse(
+            |error, _| error.into(),
+            |view, path, values| {
+                if let Some(operator) = view.operator()? {
+                    if !operator.accepts(values.len()) {
+                        return Err(DecodeError::new(
+                            DecodeErrorKind::WrongArity(operator, values.len()),
+                            view.offset,
+                        )
+                        .into());
+                    }
+                }
+                combine(view, path, values)
+            },
+        )?;
+        Ok(value)
+    }
+
+    // iterative like the parser so deep transmissions can't overflow the stack, gives back
+    // the folded value along with where the packet ends. locate turns decode errors into E
+    // given the path of the packet being read
+    pub(crate) fn traverse<T, E>(
+        &self,
+        locate: impl Fn(DecodeError, &[usize]) -> E,
+        mut combine: impl FnMut(PacketView<'a>, &[usize], Vec<T>) -> Result<T, E>,
+    ) -> Result<(T, usize), E> {
+        let mut open: Vec<Open<'a, T>> = Vec::new();
+        let mut path = Vec::new();
+        let mut offset = self.offset;
+
+        loop {
+            let view = PacketView {
+                reader: self.reader,
+                offset,
+            };
+            let header = read_header(self.reader, offset).map_err(|error| locate(error, &path))?;
+            let mut finished = match header.body {
+                Body::Literal(_) => Some((combine(view, &path, Vec::new())?, header.end)),
+                Body::Operator(_, ending) => {
+                    open.push(Open {
+                        view,
+                        ending,
+                        position: header.end,
+                        values: Vec::new(),
+                    });
+                    None
+                }
+            };
+
+            // hand finished packets to their parents until one still has sub-packets to read
+            loop {
+                let frame = match open.last_mut() {
+                    Some(frame) => frame,
+                    None => return Ok(finished.unwrap()),
+                };
+
+                if let Some((value, end)) = finished.take() {
+                    frame.values.push(value);
+                    frame.position = end;
+                    path.pop();
+                    if let Ending::Position(ending) = frame.ending {
+                        if end > ending {
+                            let error = DecodeError::new(DecodeErrorKind::LengthMismatch, end);
+                            return Err(locate(error, &path));
+                        }
+                    }
+                }
+
+                let complete = match frame.ending {
+                    Ending::Position(ending) => frame.position >= ending,
+                    Ending::Count(count) => frame.values.len() as u64 == count,
+                };
+                if !complete {
+                    path.push(frame.values.len());
+                    offset = frame.position;
+                    break;
+                }
+
+                let frame = open.pop().unwrap();
+                finished = Some((combine(frame.view, &path, frame.values)?, frame.position));
+            }
+        }
+    }
+}
+
+// an operator whose sub-packets are still being folded
+struct Open<'a, T> {
+    view: PacketView<'a>,
+    ending: Ending,
+    position: usize,
+    values: Vec<T>,
+}
+
+pub struct Children<'a> {
+    reader: &'a HexReader,
+    position: usize,
+    ending: Option<Ending>,
+    count: u64,
+    error: Option<DecodeError>,
+}
+
+impl<'a> Children<'a> {
+    // just past the last sub-packet stepped over, so once exhausted where the packet ends
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = Result<PacketView<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.ending = None;
+            return Some(Err(error));
+        }
+
+        let done = match self.ending.as_ref()? {
+            Ending::Position(ending) => self.position >= *ending,
+            Ending::Count(count) => self.count == *count,
+        };
+        if done {
+            self.ending = None;
+            return None;
+        }
+
+        let child = PacketView {
+            reader: self.reader,
+            offset: self.position,
+        };
+        match child.end() {
+            Ok(end) => {
+                self.position = end;
+                self.count += 1;
+                if let Some(Ending::Position(ending)) = self.ending {
+                    if end > ending {
+                        self.error = Some(DecodeError::new(DecodeErrorKind::LengthMismatch, end));
+                    }
+                }
+                Some(Ok(child))
+            }
+            Err(error) => {
+                self.ending = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{deep_transmission, EXAMPLES};
+    use crate::parse;
+
+    #[test]
+    fn test_matches_owned_tree() {
+        for example in EXAMPLES {
+            let packet = parse(example).unwrap();
+            let reader = HexReader::new(example).unwrap();
+            let view = PacketView::new(&reader);
+
+            assert_eq!(Ok(packet.version), view.version());
+            assert_eq!(Ok(packet.version_sum()), view.version_sum());
+            assert_eq!(Ok(packet.apply()), view.apply());
+        }
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let reader = HexReader::new("EE00D40C823060").unwrap();
+        let view = PacketView::new(&reader);
+        assert_eq!(Ok(7), view.version());
+        assert_eq!(Ok(Some(Operator::Maximum)), view.operator());
+        assert_eq!(Ok(3), view.child_count());
+        assert_eq!(Ok(51), view.end());
+
+        let children: Vec<_> = view.children().map(|child| child.unwrap()).collect();
+        assert_eq!(
+            vec![18, 29, 40],
+            children.iter().map(|c| c.offset()).collect::<Vec<_>>()
+        );
+        assert_eq!(Ok(Some(BigUint::from(2u64))), children[1].literal());
+        assert_eq!(Ok(None), view.literal());
+        assert_eq!(0, children[0].children().count());
+
+        let reader = HexReader::new("38006F45291200").unwrap();
+        assert_eq!(Ok(2), PacketView::new(&reader).child_count());
+    }
+
+    #[test]
+    fn test_lazy_headers() {
+        // the counted sub-packets run past the end of the transmission, which only
+        // matters once something actually steps over them
+        let reader = HexReader::new("EE00D40C82").unwrap();
+        let view = PacketView::new(&reader);
+        assert_eq!(Ok(7), view.version());
+        assert_eq!(Ok(3), view.child_count());
+
+        let children: Vec<_> = view.children().collect();
+        assert_eq!(3, children.len());
+        // the third sub-packet starts right where the transmission stops
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::Truncated, 40)),
+            children[2].map(|child| child.offset())
+        );
+        assert_eq!(
+            Err(ViewError::Decode(DecodeError::new(
+                DecodeErrorKind::Truncated,
+                40
+            ))),
+            view.apply()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        // less than with a single sub-packet
+        let reader = HexReader::new("38002C408").unwrap();
+        assert_eq!(
+            Err(ViewError::Decode(DecodeError::new(
+                DecodeErrorKind::WrongArity(Operator::LessThan, 1),
+                0
+            ))),
+            PacketView::new(&reader).apply()
+        );
+        // a sum of no sub-packets has no version sum either
+        let reader = HexReader::new("02000").unwrap();
+        assert_eq!(
+            Err(DecodeError::new(
+                DecodeErrorKind::WrongArity(Operator::Sum, 0),
+                0
+            )),
+            PacketView::new(&reader).version_sum()
+        );
+
+        // sub-packets overrunning their declared length
+        let reader = HexReader::new("200028408").unwrap();
+        let children: Vec<_> = PacketView::new(&reader).children().collect();
+        assert_eq!(2, children.len());
+        assert_eq!(
+            Err(DecodeError::new(DecodeErrorKind::LengthMismatch, 33)),
+            children[1].map(|child| child.offset())
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let input = deep_transmission(100_000);
+        let reader = HexReader::new(&input).unwrap();
+        let view = PacketView::new(&reader);
+        assert_eq!(Ok(100_000), view.version_sum());
+        assert_eq!(Ok(7), view.apply());
+        assert_eq!(Ok(100_000 * 18 + 11), view.end());
+
+        let mut children = view.children();
+        assert_eq!(
+            Some(18),
+            children.next().map(|child| child.unwrap().offset())
+        );
+        assert_eq!(None, children.next().map(|child| child.unwrap().offset()));
+        assert_eq!(100_000 * 18 + 11, children.position());
+    }
+}
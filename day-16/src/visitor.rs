@@ -0,0 +1,165 @@
+use crate::{Packet, Payload};
+use std::convert::Infallible;
+
+// callbacks for walking a packet tree, path holds the index of each sub-packet taken
+// from the outermost packet down so its length is the packet's depth
+pub trait PacketVisitor {
+    fn enter(&mut self, _packet: &Packet, _path: &[usize]) {}
+    fn leave(&mut self, _packet: &Packet, _path: &[usize]) {}
+}
+
+enum Step {
+    Enter,
+    Leave,
+}
+
+type Visit<'a, E> = dyn FnMut(Step, &Packet, &[usize]) -> Result<(), E> + 'a;
+
+impl Packet {
+    // calls enter on a packet before any of its sub-packets and leave after all of them
+    pub fn walk(&self, visitor: &mut impl PacketVisitor) {
+        let result = self.traverse::<Infallible>(&mut |step, packet, path| {
+            match step {
+                Step::Enter => visitor.enter(packet, path),
+                Step::Leave => visitor.leave(packet, path),
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => (),
+            Err(never) => match never {},
+        }
+    }
+
+    // combines every packet with the results of its sub-packets, innermost first
+    pub fn fold<T>(&self, mut combine: impl FnMut(&Packet, Vec<T>) -> T) -> T {
+        let result =
+            self.try_fold(|packet, _, values| Ok::<T, Infallible>(combine(packet, values)));
+        match result {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    // fold that stops at the first error, combine is also given the packet's path
+    pub fn try_fold<T, E>(
+        &self,
+        mut combine: impl FnMut(&Packet, &[usize], Vec<T>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        // the results gathered so far for each open packet, plus one for the outermost
+        let mut values: Vec<Vec<T>> = vec![Vec::new()];
+        self.traverse(&mut |step, packet, path| {
+            match step {
+                Step::Enter => values.push(Vec::new()),
+                Step::Leave => {
+                    let value = combine(packet, path, values.pop().unwrap())?;
+                    values.last_mut().unwrap().push(value);
+                }
+            }
+            Ok(())
+        })?;
+        Ok(values.pop().unwrap().pop().unwrap())
+    }
+
+    // iterative so that deeply nested transmissions can't overflow the stack
+    fn traverse<E>(&self, visit: &mut Visit<'_, E>) -> Result<(), E> {
+        let mut path = Vec::new();
+        // each open packet along with the index of the next sub-packet to visit
+        let mut open: Vec<(&Packet, usize)> = vec![(self, 0)];
+        visit(Step::Enter, self, &path)?;
+
+        while let Some((packet, next)) = open.last_mut() {
+            let packet: &Packet = packet;
+            let subpackets: &[Packet] = match &packet.payload {
+                Payload::Literal(_) => &[],
+                Payload::Operator(_, packets) => packets,
+            };
+
+            match subpackets.get(*next) {
+                Some(subpacket) => {
+                    path.push(*next);
+                    *next += 1;
+                    visit(Step::Enter, subpacket, &path)?;
+                    open.push((subpacket, 0));
+                }
+                None => {
+                    visit(Step::Leave, packet, &path)?;
+                    open.pop();
+                    path.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    struct Trace(Vec<String>);
+
+    impl PacketVisitor for Trace {
+        fn enter(&mut self, packet: &Packet, path: &[usize]) {
+            self.0.push(format!("enter v{} {:?}", packet.version, path));
+        }
+
+        fn leave(&mut self, packet: &Packet, path: &[usize]) {
+            self.0.push(format!("leave v{} {:?}", packet.version, path));
+        }
+    }
+
+    #[test]
+    fn test_walk_order() {
+        // maximum of literals 1, 2 and 3 under versions 7, 2, 4 and 1
+        let mut trace = Trace(Vec::new());
+        parse("EE00D40C823060").unwrap().walk(&mut trace);
+        assert_eq!(
+            vec![
+                "enter v7 []",
+                "enter v2 [0]",
+                "leave v2 [0]",
+                "enter v4 [1]",
+                "leave v4 [1]",
+                "enter v1 [2]",
+                "leave v1 [2]",
+                "leave v7 []",
+            ],
+            trace.0
+        );
+    }
+
+    #[test]
+    fn test_fold_analyses() {
+        let packet = parse("A0016C880162017C3686B18A3D4780").unwrap();
+
+        let depth = packet.fold(|_, depths| 1 + depths.into_iter().max().unwrap_or(0));
+        assert_eq!(4, depth);
+
+        let literals = packet.fold(|packet, counts: Vec<usize>| match packet.payload {
+            Payload::Literal(_) => 1,
+            Payload::Operator(..) => counts.into_iter().sum(),
+        });
+        assert_eq!(5, literals);
+
+        let max_version =
+            packet.fold(|packet, versions| versions.into_iter().fold(packet.version, u64::max));
+        assert_eq!(7, max_version);
+    }
+
+    #[test]
+    fn test_try_fold_stops() {
+        let packet = parse("EE00D40C823060").unwrap();
+        let mut visited = Vec::new();
+        let result = packet.try_fold(|packet, path, _: Vec<()>| {
+            visited.push(path.to_vec());
+            match packet.version {
+                4 => Err(path.to_vec()),
+                _ => Ok(()),
+            }
+        });
+        assert_eq!(Err(vec![1]), result);
+        assert_eq!(vec![vec![0], vec![1]], visited);
+    }
+}
@@ -1,10 +1,12 @@
 mod probe;
+mod solver;
 
 use common::bounds::Bounds;
 use common::coordinate::Coordinate;
 use common::direction::Direction;
 use probe::Probe;
 use regex::Regex;
+use solver::solve;
 use std::cmp::max;
 
 fn parse(value: &str) -> Bounds {
@@ -71,8 +73,13 @@ fn is_undershot(position: &Coordinate, target: &Bounds) -> bool {
 
 fn main() {
     let bounds = parse("target area: x=253..280, y=-73..-46");
-    println!("Part 1: {}", run_tests(&bounds, 1000).0);
-    println!("Part 2: {}", run_tests(&bounds, 1000).1);
+    // day-17 --brute-force flies every probe in the grid instead, to check the solver against
+    let (best, hits) = match std::env::args().nth(1).as_deref() {
+        Some("--brute-force") => run_tests(&bounds, 1000),
+        _ => solve(&bounds),
+    };
+    println!("Part 1: {}", best);
+    println!("Part 2: {}", hits);
 }
 
 #[cfg(test)]
@@ -126,5 +133,19 @@ mod test {
         let result = run_tests(&bounds, 31);
         assert_eq!(45, result.0);
         assert_eq!(112, result.1);
+        assert_eq!(result, solve(&bounds));
+    }
+
+    #[test]
+    fn test_solver_matches_brute_force() {
+        for target in [
+            "target area: x=253..280, y=-73..-46",
+            "target area: x=1..3, y=-4..-1",
+            "target area: x=15..40, y=-30..-29",
+            "target area: x=100..101, y=-5..-1",
+        ] {
+            let bounds = parse(target);
+            assert_eq!(run_tests(&bounds, 300), solve(&bounds), "{}", target);
+        }
     }
 }
@@ -0,0 +1,136 @@
+use common::bounds::Bounds;
+use std::cmp::{max, min};
+
+// after t steps a probe launched at vx has moved x = t*vx - t(t-1)/2 until drag stops it
+// at the triangular number vx(vx+1)/2, while one launched at vy is at y = t*vy - t(t-1)/2
+fn x_at(vx: i64, t: i64) -> i64 {
+    let moving = min(t, vx);
+    moving * vx - moving * (moving - 1) / 2
+}
+
+fn y_at(vy: i64, t: i64) -> i64 {
+    t * vy - t * (t - 1) / 2
+}
+
+// the step at which v*t - t(t-1)/2 = position, taking the root on the given side
+fn root(velocity: i64, position: i64, later: bool) -> f64 {
+    let b = (2 * velocity + 1) as f64;
+    let discriminant = (b * b - 8.0 * position as f64).max(0.0).sqrt();
+    match later {
+        true => (b + discriminant) / 2.0,
+        false => (b - discriminant) / 2.0,
+    }
+}
+
+// the first step from 1 up where reached holds, which has to stay true after that,
+// starting from an estimate that floating point might have put a step or so out
+fn first_step(estimate: f64, reached: impl Fn(i64) -> bool) -> i64 {
+    let mut step = max(estimate.ceil() as i64, 1);
+    while !reached(step) {
+        step += 1;
+    }
+    while step > 1 && reached(step - 1) {
+        step -= 1;
+    }
+    step
+}
+
+// steps a horizontal velocity spends between left and right, unbounded when it stops there
+fn x_window(vx: i64, left: i64, right: i64) -> Option<(i64, i64)> {
+    if vx * (vx + 1) / 2 < left {
+        return None;
+    }
+
+    let first = first_step(root(vx, left, false), |t| x_at(vx, t) >= left);
+    let last = match vx * (vx + 1) / 2 <= right {
+        true => i64::MAX,
+        false => first_step(root(vx, right, false), |t| x_at(vx, t) > right) - 1,
+    };
+    match first <= last {
+        true => Some((first, last)),
+        false => None,
+    }
+}
+
+// steps a vertical velocity spends between bottom and top, the target being below the
+// launcher means the probe is always on its way down by then
+fn y_window(vy: i64, bottom: i64, top: i64) -> Option<(i64, i64)> {
+    let first = first_step(root(vy, top, true), |t| y_at(vy, t) <= top);
+    let last = first_step(root(vy, bottom, true), |t| y_at(vy, t) < bottom) - 1;
+    match first <= last {
+        true => Some((first, last)),
+        false => None,
+    }
+}
+
+// the same answer as run_tests without flying any probes: every velocity whose horizontal
+// and vertical step windows overlap hits, returning the highest point and number of hits
+pub fn solve(target: &Bounds) -> (i32, u32) {
+    let left = min(target.from.0, target.to.0) as i64;
+    let right = max(target.from.0, target.to.0) as i64;
+    let bottom = min(target.from.1, target.to.1) as i64;
+    let top = max(target.from.1, target.to.1) as i64;
+    assert!(
+        left > 0 && top < 0,
+        "the target has to be ahead of and below the launcher"
+    );
+
+    // anything faster overshoots on the first step, and a probe thrown up at vy comes
+    // back down through 0 at -(vy + 1) so has to be slower than the bottom is deep
+    let x_windows: Vec<_> = (1..=right)
+        .filter_map(|vx| x_window(vx, left, right))
+        .collect();
+
+    let mut best = 0;
+    let mut hits = 0;
+    for vy in bottom..-bottom {
+        let (first, last) = match y_window(vy, bottom, top) {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let overlapping = x_windows
+            .iter()
+            .filter(|(x_first, x_last)| max(first, *x_first) <= min(last, *x_last))
+            .count();
+        if overlapping > 0 {
+            hits += overlapping as u32;
+            best = max(best, max(vy, 0) * (max(vy, 0) + 1) / 2);
+        }
+    }
+    (best as i32, hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::coordinate::Coordinate;
+
+    #[test]
+    fn test_positions() {
+        assert_eq!(28, x_at(7, 7));
+        assert_eq!(28, x_at(7, 20));
+        assert_eq!(25, x_at(7, 5));
+        assert_eq!(-7, y_at(2, 7));
+        assert_eq!(3, y_at(2, 3));
+    }
+
+    #[test]
+    fn test_windows() {
+        // from the example, 7,2 is in the target at step 7 and 6,9 at step 20
+        assert_eq!(Some((7, 7)), y_window(2, -10, -5));
+        assert_eq!(Some((20, 20)), y_window(9, -10, -5));
+        assert_eq!(Some((5, i64::MAX)), x_window(6, 20, 30));
+        assert_eq!(Some((4, i64::MAX)), x_window(7, 20, 30));
+        assert_eq!(Some((3, 5)), x_window(8, 20, 30));
+        assert_eq!(None, x_window(5, 20, 30));
+        assert_eq!(None, x_window(31, 20, 30));
+        assert_eq!(Some((1, 1)), x_window(30, 20, 30));
+    }
+
+    #[test]
+    fn test_example() {
+        let target = Bounds::new(Coordinate(20, -10), Coordinate(30, -5));
+        assert_eq!((45, 112), solve(&target));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::scanner::Scanner;
+use common::coordinate::Coordinate;
+use common::coordinate_3d::Coordinate3D;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+// anything a scanner can report a beacon as, the alignment below only relies on
+// being able to re-orient, translate and measure points of the same dimension
+pub trait Point: Copy + Eq + Hash + Add<Output = Self> + Sub<Output = Self> {
+    const ORIENTATIONS: usize;
+    const ORIGIN: Self;
+
+    fn orient(&self, orientation: usize) -> Self;
+    fn distance(&self, other: &Self) -> f32;
+}
+
+impl Point for Coordinate {
+    const ORIENTATIONS: usize = 8;
+    const ORIGIN: Self = Coordinate(0, 0);
+
+    fn orient(&self, orientation: usize) -> Self {
+        Coordinate::orient(self, orientation)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Coordinate::distance(self, other)
+    }
+}
+
+impl Point for Coordinate3D {
+    const ORIENTATIONS: usize = 24;
+    const ORIGIN: Self = Coordinate3D(0, 0, 0);
+
+    fn orient(&self, orientation: usize) -> Self {
+        Coordinate3D::orient(self, orientation)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Coordinate3D::distance(self, other)
+    }
+}
+
+pub struct Alignment<P: Point> {
+    pub beacons: HashSet<P>,
+    pub positions: Vec<P>,
+}
+
+// places every scanner relative to the first one, returning the ids of any scanners
+// which never share at least threshold beacons with one that's already been placed
+pub fn align<P: Point>(
+    scanners: &[Scanner<P>],
+    threshold: usize,
+) -> Result<Alignment<P>, Vec<u32>> {
+    let first = match scanners.first() {
+        Some(first) => first,
+        None => {
+            return Ok(Alignment {
+                beacons: HashSet::new(),
+                positions: Vec::new(),
+            })
+        }
+    };
+
+    let mut placed: Vec<Option<(P, Vec<P>)>> = vec![None; scanners.len()];
+    placed[0] = Some((P::ORIGIN, first.beacons.clone()));
+
+    let mut pending = vec![0];
+    while let Some(reference) = pending.pop() {
+        let known = placed[reference].as_ref().unwrap().1.clone();
+
+        for (index, scanner) in scanners.iter().enumerate() {
+            if placed[index].is_some() || !shares_beacons(&scanners[reference], scanner, threshold)
+            {
+                continue;
+            }
+
+            if let Some(found) = locate(&known, scanner, threshold) {
+                placed[index] = Some(found);
+                pending.push(index);
+            }
+        }
+    }
+
+    let unplaced: Vec<u32> = scanners
+        .iter()
+        .zip(placed.iter())
+        .filter(|(_, found)| found.is_none())
+        .map(|(scanner, _)| scanner.id)
+        .collect();
+    if !unplaced.is_empty() {
+        return Err(unplaced);
+    }
+
+    let mut alignment = Alignment {
+        beacons: HashSet::new(),
+        positions: Vec::new(),
+    };
+
+    for (position, beacons) in placed.into_iter().flatten() {
+        alignment.positions.push(position);
+        alignment.beacons.extend(beacons);
+    }
+
+    Ok(alignment)
+}
+
+// tries every orientation of the scanner, counting how often each translation maps
+// one of its beacons onto a known one, enough agreement fixes both
+fn locate<P: Point>(known: &[P], scanner: &Scanner<P>, threshold: usize) -> Option<(P, Vec<P>)> {
+    for orientation in 0..P::ORIENTATIONS {
+        let oriented: Vec<P> = scanner
+            .beacons
+            .iter()
+            .map(|beacon| beacon.orient(orientation))
+            .collect();
+
+        let mut offsets: HashMap<P, usize> = HashMap::new();
+        for known in known {
+            for beacon in &oriented {
+                *offsets.entry(*known - *beacon).or_insert(0) += 1;
+            }
+        }
+
+        if let Some((&position, _)) = offsets.iter().find(|(_, &count)| count >= threshold) {
+            let beacons = oriented
+                .into_iter()
+                .map(|beacon| beacon + position)
+                .collect();
+            return Some((position, beacons));
+        }
+    }
+    None
+}
+
+// distances between beacons don't change with orientation, so two scanners which
+// overlap must have a beacon each seeing threshold - 1 of the same distances
+fn shares_beacons<P: Point>(left: &Scanner<P>, right: &Scanner<P>, threshold: usize) -> bool {
+    left.distances.values().any(|left| {
+        right
+            .distances
+            .values()
+            .any(|right| intersections(left, right) + 1 >= threshold)
+    })
+}
+
+fn intersections(left: &[f32], right: &[f32]) -> usize {
+    let mut count = 0;
+    for left in left.iter() {
+        if right.contains(left) {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_INPUT: &str = include_str!("input.example.txt");
+    const EXAMPLE_INPUT_2D: &str = include_str!("input.example.2d.txt");
+
+    #[test]
+    fn test_intersections() {
+        assert_eq!(1, intersections(&[1.0], &[1.0]));
+        assert_eq!(0, intersections(&[1.0], &[2.0]));
+        assert_eq!(2, intersections(&[1.0, 2.0], &[1.0, 2.0]));
+        assert_eq!(1, intersections(&[1.0, 2.0], &[1.0, 3.0]));
+        assert_eq!(0, intersections(&[1.0, 2.0], &[3.0, 4.0]));
+        assert_eq!(1, intersections(&[1.0, 2.0], &[3.0, 4.0, 1.0]));
+        assert_eq!(2, intersections(&[4.0, 5.0, 1.0, 2.0], &[4.0, 1.0]))
+    }
+
+    #[test]
+    fn align_3d() {
+        let scanners = Scanner::<Coordinate3D>::parse(EXAMPLE_INPUT);
+        let alignment = align(&scanners, 12).unwrap();
+
+        assert_eq!(79, alignment.beacons.len());
+        assert_eq!(Coordinate3D(68, -1246, -43), alignment.positions[1]);
+        assert_eq!(Coordinate3D(1105, -1205, 1229), alignment.positions[2]);
+        assert_eq!(Coordinate3D(-92, -2380, -20), alignment.positions[3]);
+        assert_eq!(Coordinate3D(-20, -1133, 1061), alignment.positions[4]);
+    }
+
+    #[test]
+    fn align_2d() {
+        let scanners = Scanner::<Coordinate>::parse(EXAMPLE_INPUT_2D);
+        let alignment = align(&scanners, 3).unwrap();
+
+        assert_eq!(9, alignment.beacons.len());
+        assert_eq!(
+            vec![Coordinate(0, 0), Coordinate(41, -17), Coordinate(83, -5)],
+            alignment.positions
+        );
+        assert!(alignment.beacons.contains(&Coordinate(110, 7)));
+    }
+
+    #[test]
+    fn align_unreachable() {
+        let scanners = Scanner::<Coordinate>::parse(
+            "--- scanner 0 ---\n0,0\n1,0\n0,3\n\n--- scanner 1 ---\n0,0\n5,5\n",
+        );
+        assert_eq!(Err(vec![1]), align(&scanners, 3).map(|a| a.beacons.len()));
+    }
+
+    #[test]
+    fn align_nothing() {
+        let alignment = align::<Coordinate>(&[], 3).unwrap();
+        assert!(alignment.beacons.is_empty());
+        assert!(alignment.positions.is_empty());
+    }
+}
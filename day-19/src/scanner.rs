@@ -1,13 +1,15 @@
-use common::coordinate_3d::Coordinate3D;
+use crate::alignment::Point;
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::str::FromStr;
 
-pub struct Scanner {
+pub struct Scanner<P: Point> {
     pub id: u32,
-    pub beacons: Vec<Coordinate3D>,
-    pub distances: HashMap<Coordinate3D, Vec<f32>>,
+    pub beacons: Vec<P>,
+    pub distances: HashMap<P, Vec<f32>>,
 }
-impl Scanner {
-    pub fn new(id: u32) -> Scanner {
+impl<P: Point> Scanner<P> {
+    pub fn new(id: u32) -> Scanner<P> {
         Scanner {
             id,
             beacons: Vec::new(),
@@ -16,18 +18,18 @@ impl Scanner {
     }
 
     // updates all beacon distances, inserts the beacon along with their distances to other beacons
-    pub fn insert(&mut self, new: Coordinate3D) {
+    pub fn insert(&mut self, new: P) {
         for beacon in &self.beacons {
             let distances = self.distances.get_mut(beacon).unwrap();
             distances.push(beacon.distance(&new));
         }
 
-        self.beacons.push(new.clone());
+        self.beacons.push(new);
 
         let mut distances = Vec::new();
         for beacon in &self.beacons {
             match new.distance(beacon) {
-                distance if distance == 0.0 => continue,
+                0.0 => continue,
                 distance => distances.push(distance),
             }
         }
@@ -35,7 +37,11 @@ impl Scanner {
         self.distances.insert(new, distances);
     }
 
-    pub fn parse(input: &str) -> Vec<Scanner> {
+    pub fn parse(input: &str) -> Vec<Scanner<P>>
+    where
+        P: FromStr,
+        P::Err: Debug,
+    {
         let chunks: Vec<_> = input.split("\n\n").collect();
 
         chunks
@@ -52,7 +58,7 @@ impl Scanner {
                     .unwrap();
                 let mut scanner = Scanner::new(id);
                 for line in lines {
-                    scanner.insert(line.parse::<Coordinate3D>().unwrap());
+                    scanner.insert(line.parse::<P>().unwrap());
                 }
                 scanner
             })
@@ -63,12 +69,15 @@ impl Scanner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::coordinate::Coordinate;
+    use common::coordinate_3d::Coordinate3D;
 
     const EXAMPLE_INPUT: &str = include_str!("input.example.txt");
+    const EXAMPLE_INPUT_2D: &str = include_str!("input.example.2d.txt");
 
     #[test]
     fn parse_scanners() {
-        let scanners = Scanner::parse(EXAMPLE_INPUT);
+        let scanners = Scanner::<Coordinate3D>::parse(EXAMPLE_INPUT);
         assert_eq!(5, scanners.len());
 
         let scanner = &scanners[0];
@@ -93,4 +102,16 @@ mod tests {
         assert_eq!(Coordinate3D(605, 423, 415), scanner.beacons[1]);
         assert_eq!(Coordinate3D(515, 917, -361), scanner.beacons[2]);
     }
+
+    #[test]
+    fn parse_scanners_2d() {
+        let scanners = Scanner::<Coordinate>::parse(EXAMPLE_INPUT_2D);
+        assert_eq!(3, scanners.len());
+
+        let scanner = &scanners[1];
+        assert_eq!(1, scanner.id);
+        assert_eq!(6, scanner.beacons.len());
+        assert_eq!(Coordinate(2, 19), scanner.beacons[0]);
+        assert_eq!(5, scanner.distances.get(&Coordinate(2, 19)).unwrap().len());
+    }
 }